@@ -0,0 +1,124 @@
+//! Measures the cost of `AsyncSnapshot` operations on a large state.
+//!
+//! Run with `cargo bench --bench snapshot`.
+//! Each operation is compared with a deep clone of the state,
+//! which is what every update used to cost.
+//! Modifying the state still clones it once (copy-on-write);
+//! only operations that leave it unmodified avoid the clone.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use promkit_async::snapshot::AsyncSnapshot;
+
+const ITEMS: usize = 100_000;
+const ITERATIONS: u32 = 200;
+
+type Items = Vec<Arc<str>>;
+
+fn large_state() -> Items {
+    (0..ITEMS).map(|i| Arc::from(format!("item-{i}"))).collect()
+}
+
+fn report(name: &str, elapsed: Duration) {
+    println!("{name:<32} {:>12.2?}/iter", elapsed / ITERATIONS);
+}
+
+async fn bench<F, Fut>(name: &str, mut f: F)
+where
+    F: FnMut(u32) -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    let start = Instant::now();
+    for i in 0..ITERATIONS {
+        f(i).await;
+    }
+    report(name, start.elapsed());
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let state = large_state();
+
+    bench("deep clone (baseline)", |_| {
+        let cloned = state.clone();
+        async move {
+            std::hint::black_box(cloned);
+        }
+    })
+    .await;
+
+    let snapshot = AsyncSnapshot::new(state.clone());
+    let _subscriber = snapshot.subscribe();
+    let _changes = snapshot.changes();
+
+    let shared = Arc::new(state.clone());
+    bench("update with shared Arc", |_| {
+        let snapshot = snapshot.clone();
+        let shared = shared.clone();
+        async move { snapshot.update(shared).await }
+    })
+    .await;
+
+    bench("transaction without changes", |_| {
+        let snapshot = snapshot.clone();
+        async move {
+            let transaction = snapshot.transaction().await;
+            std::hint::black_box(transaction.state().len());
+            transaction.commit().await.unwrap();
+        }
+    })
+    .await;
+
+    bench("transaction with change", |i| {
+        let snapshot = snapshot.clone();
+        async move {
+            let mut transaction = snapshot.transaction().await;
+            transaction.state_mut()[0] = Arc::from(format!("changed-{i}"));
+            transaction.commit().await.unwrap();
+        }
+    })
+    .await;
+
+    bench("current_mut without changes", |_| {
+        let snapshot = snapshot.clone();
+        async move {
+            snapshot
+                .current_mut(|state| async move { (state, ()) })
+                .await
+        }
+    })
+    .await;
+
+    bench("current_mut with change", |i| {
+        let snapshot = snapshot.clone();
+        async move {
+            snapshot
+                .current_mut(|mut state| async move {
+                    Arc::make_mut(&mut state)[0] = Arc::from(format!("changed-{i}"));
+                    (state, ())
+                })
+                .await
+        }
+    })
+    .await;
+
+    bench("undo + redo", |_| {
+        let snapshot = snapshot.clone();
+        async move {
+            snapshot.undo().await;
+            snapshot.redo().await;
+        }
+    })
+    .await;
+
+    bench("history", |_| {
+        let snapshot = snapshot.clone();
+        async move {
+            std::hint::black_box(snapshot.history().await);
+        }
+    })
+    .await;
+}
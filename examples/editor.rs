@@ -1,18 +1,21 @@
-use std::time::Duration;
+use std::{collections::HashSet, time::Duration};
 
 use crossterm::terminal;
 use promkit::{
     crossterm::style::Color,
+    grapheme::StyledGraphemes,
     style::StyleBuilder,
     text_editor::{self},
 };
 use promkit_async::{
-    component::{Evaluator, InputProcessor},
-    Prompt,
+    component::{Evaluator, InputProcessor, TextEditor, TimeTravel},
+    event::{EventFilter, EventKind},
+    snapshot::Recorder,
+    BackpressurePolicy, Prompt, Subscriber,
 };
 
 mod editorutil;
-use editorutil::component::{EditorComponent, HeavySyncComponent};
+use editorutil::component::{HeavySyncComponent, UndoableEditor};
 use tokio::sync::mpsc;
 
 pub struct Editor {
@@ -31,7 +34,7 @@ impl Default for Editor {
                 active_char_style: StyleBuilder::new().bgc(Color::DarkCyan).build(),
                 inactive_char_style: StyleBuilder::new().build(),
                 edit_mode: Default::default(),
-                word_break_chars: Default::default(),
+                word_break_chars: HashSet::from([' ']),
                 lines: Default::default(),
             },
         }
@@ -40,35 +43,57 @@ impl Default for Editor {
 
 impl Editor {
     pub async fn run(self) -> anyhow::Result<()> {
-        let (sync_tx, sync_rx) = mpsc::channel(1);
-
-        let mut component1 = EditorComponent::new(self.text_editor_state.clone(), sync_tx)?;
-        let mut component2 = HeavySyncComponent::new(self.text_editor_state)?;
+        // Ctrl+Z undoes and Alt+Z redoes the changes of the text.
+        let mut component1 = UndoableEditor::new(TextEditor::new(self.text_editor_state.clone()));
+        // component2 follows the text of component1, undone or not.
+        let queries = component1.queries();
+        let recorder = Recorder::new(100);
+        let mut component2 =
+            HeavySyncComponent::new(self.text_editor_state, recorder.clone()).await?;
+        // Step through the states of component2 with the up/down keys.
+        let mut devtools = TimeTravel::new(recorder, |state: &text_editor::State| {
+            vec![StyledGraphemes::from(state.texteditor.text().to_string())]
+        });
 
         let (event1_tx, event1_rx) = mpsc::channel(1);
         let (event2_tx, event2_rx) = mpsc::channel(1);
+        let (event3_tx, event3_rx) = mpsc::channel(1);
         let (pane1_tx, pane1_rx) = mpsc::channel(1);
         let (pane2_tx, pane2_rx) = mpsc::channel(1);
+        let (pane3_tx, pane3_rx) = mpsc::channel(1);
 
         let terminal_area = terminal::size()?;
         let handle1 =
             tokio::spawn(async move { component1.run(terminal_area, event1_rx, pane1_tx).await });
         let handle2 = tokio::spawn(async move {
             component2
-                .run(terminal_area, sync_rx, event2_rx, pane2_tx)
+                .run(terminal_area, queries, event2_rx, pane2_tx)
                 .await
         });
+        let handle3 =
+            tokio::spawn(async move { devtools.run(terminal_area, event3_rx, pane3_tx).await });
 
         Prompt {}
             .run(
-                vec![event1_tx, event2_tx],
-                vec![pane1_rx, pane2_rx],
+                vec![
+                    // The up/down keys belong to devtools.
+                    Subscriber::new(event1_tx).filter(EventFilter::predicate(|event| {
+                        event.kind() != EventKind::VerticalCursorBuffer
+                    })),
+                    Subscriber::new(event2_tx)
+                        .filter(EventFilter::kinds([EventKind::HorizontalCursorBuffer]))
+                        .policy(BackpressurePolicy::Coalesce),
+                    Subscriber::new(event3_tx)
+                        .filter(EventFilter::kinds([EventKind::VerticalCursorBuffer])),
+                ],
+                vec![pane1_rx, pane2_rx, pane3_rx],
                 Duration::from_millis(100),
             )
             .await?;
 
         handle1.abort();
         handle2.abort();
+        handle3.abort();
         Ok(())
     }
 }
@@ -1,45 +1,89 @@
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
 use promkit::{pane::Pane, switch::ActiveKeySwitcher, text_editor, PaneFactory};
 
-use tokio::{sync::mpsc, time::sleep};
+use tokio::{
+    sync::mpsc::{Receiver, Sender},
+    time::sleep,
+};
 
 use promkit_async::{
-    component::{Evaluator, InputProcessor},
-    snapshot::AsyncSnapshot,
+    component::{
+        text_editor::EditorState, Context, Evaluator, InputProcessor, LoadingIndicator,
+        QueryPolicy, TextEditor,
+    },
+    snapshot::{AsyncSnapshot, Recorder},
     Event,
 };
 
 use crate::editorutil::keymap;
 
-pub struct EditorComponent {
-    keymap: ActiveKeySwitcher<keymap::Handler>,
-    state: text_editor::State,
-    sync_tx: mpsc::Sender<String>,
+/// A `TextEditor` whose text changes can be undone and redone.
+pub struct UndoableEditor {
+    editor: TextEditor,
+    history: AsyncSnapshot<EditorState>,
 }
 
-impl EditorComponent {
-    pub fn new(state: text_editor::State, sync_tx: mpsc::Sender<String>) -> anyhow::Result<Self> {
-        Ok(Self {
-            keymap: ActiveKeySwitcher::new("default", self::keymap::default),
-            state,
-            sync_tx,
-        })
+impl UndoableEditor {
+    pub fn new(editor: TextEditor) -> Self {
+        Self {
+            history: AsyncSnapshot::new(editor.state().clone()),
+            editor,
+        }
+    }
+
+    pub fn queries(&self) -> Receiver<String> {
+        self.editor.queries()
+    }
+
+    /// Applies the edits in order, undoing or redoing in between.
+    async fn apply(&mut self, area: (u16, u16), events: Vec<Event>) -> Pane {
+        let mut edits = Vec::new();
+        for event in events {
+            let Some((action, times)) = keymap::history(&event) else {
+                edits.push(event);
+                continue;
+            };
+            self.edit(area, std::mem::take(&mut edits)).await;
+            for _ in 0..times {
+                let restored = match action {
+                    keymap::HistoryAction::Undo => self.history.undo().await,
+                    keymap::HistoryAction::Redo => self.history.redo().await,
+                };
+                let Some(state) = restored else {
+                    break;
+                };
+                self.editor.set_state((*state).clone());
+            }
+        }
+        self.edit(area, edits).await
+    }
+
+    /// Records the edits that change the text.
+    async fn edit(&mut self, area: (u16, u16), events: Vec<Event>) -> Pane {
+        let text = self.editor.state().text();
+        let pane = self.editor.process_event(area, events);
+        if self.editor.state().text() != text {
+            self.history.update(self.editor.state().clone()).await;
+        }
+        pane
     }
 }
 
-impl InputProcessor<Vec<Event>> for EditorComponent {
+#[async_trait::async_trait]
+impl InputProcessor<Vec<Event>> for UndoableEditor {
+    /// Edits without recording; `run` keeps the history.
     fn process_event(&mut self, area: (u16, u16), inputs: Vec<Event>) -> Pane {
-        let keymap = self.keymap.get();
-        if let Err(e) = keymap(&inputs, &mut self.state) {
-            eprintln!("Error processing event: {}", e);
+        self.editor.process_event(area, inputs)
+    }
+
+    async fn run(&mut self, area: (u16, u16), mut rx: Receiver<Vec<Event>>, tx: Sender<Pane>) {
+        while let Some(events) = rx.recv().await {
+            let pane = self.apply(area, events).await;
+            if tx.send(pane).await.is_err() {
+                break;
+            }
         }
-        let text = self.state.texteditor.text().to_string();
-        let tx = self.sync_tx.clone();
-        tokio::spawn(async move {
-            let _ = tx.send(text).await;
-        });
-        self.state.create_pane(area.0, area.1)
     }
 }
 
@@ -50,37 +94,69 @@ pub struct HeavySyncComponent {
 }
 
 impl HeavySyncComponent {
-    pub fn new(state: text_editor::State) -> anyhow::Result<Self> {
+    pub async fn new(
+        state: text_editor::State,
+        recorder: Recorder<text_editor::State>,
+    ) -> anyhow::Result<Self> {
+        let state = AsyncSnapshot::new(state);
+        state.set_recorder(recorder).await;
         Ok(Self {
             keymap: ActiveKeySwitcher::new("default", self::keymap::movement),
-            state: AsyncSnapshot::new(state),
+            state,
         })
     }
 }
 
 #[async_trait::async_trait]
 impl Evaluator for HeavySyncComponent {
-    async fn process_events(&mut self, area: (u16, u16), events: Vec<Event>) -> Pane {
+    const LOADING_INDICATOR: LoadingIndicator = LoadingIndicator {
+        label: "syncing",
+        show_elapsed: true,
+        ..LoadingIndicator::DEFAULT
+    };
+    const QUERY_POLICY: QueryPolicy = QueryPolicy {
+        debounce: Duration::from_millis(200),
+        ..QueryPolicy::DEFAULT
+    };
+
+    async fn process_events(&mut self, area: (u16, u16), events: Vec<Event>, _: Context) -> Pane {
         let keymap = self.keymap.get();
         self.state
-            .current_mut(move |mut state| async move {
-                if let Err(e) = keymap(&events, &mut state) {
-                    eprintln!("Error processing event: {}", e);
+            .current_mut(|mut state| {
+                let events = events.clone();
+                async move {
+                    if let Err(e) = keymap(&events, Arc::make_mut(&mut state)) {
+                        eprintln!("Error processing event: {}", e);
+                    }
+                    let pane = state.create_pane(area.0, area.1);
+                    (state, pane)
                 }
-                let pane = state.create_pane(area.0, area.1);
-                (state, pane)
             })
             .await
     }
 
-    async fn process_query(&mut self, area: (u16, u16), input: String) -> Pane {
-        self.state
-            .current_mut(move |mut state| async move {
-                state.texteditor.replace(&input.to_uppercase());
-                sleep(Duration::from_secs(5)).await;
-                let pane = state.create_pane(area.0, area.1);
-                (state, pane)
-            })
-            .await
+    async fn process_query(&mut self, area: (u16, u16), input: String, ctx: Context) -> Pane {
+        const STEPS: usize = 50;
+        loop {
+            let mut transaction = self.state.transaction().await;
+            transaction
+                .state_mut()
+                .texteditor
+                .replace(&input.to_uppercase());
+            for step in 1..=STEPS {
+                tokio::select! {
+                    _ = sleep(Duration::from_millis(100)) => {}
+                    // Dropping the transaction discards the half-synced state.
+                    _ = ctx.cancelled() => {
+                        return self.state.read(|state| state.create_pane(area.0, area.1));
+                    }
+                }
+                ctx.progress().set_fraction(step as f64 / STEPS as f64);
+            }
+            let pane = transaction.state().create_pane(area.0, area.1);
+            if transaction.commit().await.is_ok() {
+                return pane;
+            }
+        }
     }
 }
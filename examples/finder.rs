@@ -0,0 +1,93 @@
+use std::{collections::HashSet, path::PathBuf, time::Duration};
+
+use crossterm::terminal;
+use promkit::{crossterm::style::Color, style::StyleBuilder, text_editor};
+use promkit_async::{
+    component::{Evaluator, Finder, InputProcessor, TextEditor},
+    event::{EventFilter, EventKind},
+    Prompt, Subscriber,
+};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+/// Lists the files under `root` while the user is already typing.
+fn walk(root: PathBuf) -> ReceiverStream<String> {
+    let (tx, rx) = mpsc::channel(1024);
+    tokio::task::spawn_blocking(move || {
+        let mut dirs = vec![root];
+        while let Some(dir) = dirs.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    dirs.push(path);
+                } else if tx.blocking_send(path.display().to_string()).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    ReceiverStream::new(rx)
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut editor = TextEditor::new(text_editor::State {
+        texteditor: Default::default(),
+        history: Default::default(),
+        prefix: String::from("❯❯ "),
+        mask: Default::default(),
+        prefix_style: StyleBuilder::new().fgc(Color::DarkGreen).build(),
+        active_char_style: StyleBuilder::new().bgc(Color::DarkCyan).build(),
+        inactive_char_style: StyleBuilder::new().build(),
+        edit_mode: Default::default(),
+        word_break_chars: HashSet::from([' ', '/']),
+        lines: Some(1),
+    });
+    let queries = editor.queries();
+    let finder = Finder::new(walk(PathBuf::from(".")));
+
+    let (event1_tx, event1_rx) = mpsc::channel(1);
+    let (event2_tx, event2_rx) = mpsc::channel(1);
+    let (pane1_tx, pane1_rx) = mpsc::channel(1);
+    let (pane2_tx, pane2_rx) = mpsc::channel(1);
+
+    let (width, height) = terminal::size()?;
+    let handle1 = tokio::spawn(async move { editor.run((width, 1), event1_rx, pane1_tx).await });
+    let handle2 = {
+        let mut finder = finder.clone();
+        tokio::spawn(async move {
+            finder
+                .run(
+                    (width, height.saturating_sub(1)),
+                    queries,
+                    event2_rx,
+                    pane2_tx,
+                )
+                .await
+        })
+    };
+
+    Prompt {}
+        .run(
+            vec![
+                Subscriber::new(event1_tx).filter(EventFilter::predicate(|event| {
+                    event.kind() != EventKind::VerticalCursorBuffer
+                })),
+                Subscriber::new(event2_tx)
+                    .filter(EventFilter::kinds([EventKind::VerticalCursorBuffer])),
+            ],
+            vec![pane1_rx, pane2_rx],
+            Duration::from_millis(50),
+        )
+        .await?;
+
+    handle1.abort();
+    handle2.abort();
+    if let Some(selected) = finder.selected() {
+        println!("{}", selected);
+    }
+    Ok(())
+}
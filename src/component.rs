@@ -1,4 +1,13 @@
 pub mod input_processor;
 pub use input_processor::InputProcessor;
+pub mod devtools;
+pub use devtools::TimeTravel;
+pub mod text_editor;
+pub use text_editor::TextEditor;
+pub mod finder;
+pub use finder::Finder;
 pub mod evaluate;
-pub use evaluate::Evaluator;
+pub use evaluate::{
+    Context, Evaluator, LoadingIndicator, LoadingPosition, Monitor, PanicPolicy,
+    PendingEventsPolicy, Progress, ProgressReporter, QueryCache, QueryPolicy, Status,
+};
@@ -0,0 +1,201 @@
+use async_trait::async_trait;
+use promkit::{grapheme::StyledGraphemes, pane::Pane};
+use tokio::sync::mpsc::{Receiver, Sender};
+
+use crate::{
+    snapshot::{Record, Recorder},
+    Event,
+};
+
+use super::InputProcessor;
+
+type Render<T> = dyn Fn(&T) -> Vec<StyledGraphemes> + Send;
+
+/// Lets the user step through the states kept by a [`Recorder`]
+/// while the prompt runs.
+///
+/// Moving the cursor up steps backwards and moving it down steps forwards;
+/// stepping past the newest record follows the new records again.
+/// The pane is rendered when `run` starts,
+/// and again whenever the recorder makes a new record.
+pub struct TimeTravel<T> {
+    recorder: Recorder<T>,
+    render: Box<Render<T>>,
+    /// Version of the selected record, or `None` to follow the newest one.
+    selected: Option<u64>,
+}
+
+impl<T> TimeTravel<T> {
+    pub fn new<F>(recorder: Recorder<T>, render: F) -> Self
+    where
+        F: Fn(&T) -> Vec<StyledGraphemes> + Send + 'static,
+    {
+        Self {
+            recorder,
+            render: Box::new(render),
+            selected: None,
+        }
+    }
+
+    fn step(&mut self, records: &[Record<T>], up: usize, down: usize) {
+        let Some(last) = records.len().checked_sub(1) else {
+            return;
+        };
+        if up == down {
+            return;
+        }
+        let current = self
+            .selected
+            .map(|version| {
+                records
+                    .iter()
+                    .position(|record| record.version >= version)
+                    .unwrap_or(last)
+            })
+            .unwrap_or(last);
+        let next = (current + down).saturating_sub(up);
+        self.selected = if next >= last && down > up {
+            None
+        } else {
+            Some(records[next.min(last)].version)
+        };
+    }
+
+    fn render(&self, records: &[Record<T>]) -> Vec<StyledGraphemes> {
+        let Some(index) = self
+            .selected
+            .and_then(|version| records.iter().position(|record| record.version == version))
+            .or(records.len().checked_sub(1))
+        else {
+            return vec![StyledGraphemes::from("no records")];
+        };
+        let record = &records[index];
+
+        let mut rows = vec![
+            StyledGraphemes::from(format!(
+                "{}/{} v{} +{:.3}s{}",
+                index + 1,
+                records.len(),
+                record.version,
+                record
+                    .at
+                    .duration_since(self.recorder.started_at())
+                    .as_secs_f64(),
+                if self.selected.is_none() {
+                    " (live)"
+                } else {
+                    ""
+                },
+            )),
+            StyledGraphemes::from(match &record.events {
+                Some(events) => format!("events: {:?}", events),
+                None => String::from("events: -"),
+            }),
+        ];
+        rows.extend((self.render)(&record.state));
+        rows
+    }
+}
+
+#[async_trait]
+impl<T: Send + Sync + 'static> InputProcessor<Vec<Event>> for TimeTravel<T> {
+    fn process_event(&mut self, _area: (u16, u16), inputs: Vec<Event>) -> Pane {
+        let records = self.recorder.records();
+        for event in &inputs {
+            if let Event::VerticalCursorBuffer(up, down) = event {
+                self.step(&records, *up, *down);
+            }
+        }
+        Pane::new(self.render(&records), 0)
+    }
+
+    async fn run(&mut self, area: (u16, u16), mut rx: Receiver<Vec<Event>>, tx: Sender<Pane>) {
+        let mut recorded = self.recorder.subscribe();
+        let mut pane = self.process_event(area, Vec::new());
+        loop {
+            if tx.send(pane).await.is_err() {
+                break;
+            }
+            pane = tokio::select! {
+                inputs = rx.recv() => match inputs {
+                    Some(inputs) => self.process_event(area, inputs),
+                    None => break,
+                },
+                Ok(()) = recorded.changed() => self.process_event(area, Vec::new()),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot::AsyncSnapshot;
+
+    mod process_event {
+        use super::*;
+
+        /// Returns the header without the elapsed time.
+        fn header(time_travel: &mut TimeTravel<i32>, up: usize, down: usize) -> String {
+            let pane =
+                time_travel.process_event((80, 10), vec![Event::VerticalCursorBuffer(up, down)]);
+            pane.extract(1)[0]
+                .to_string()
+                .split(' ')
+                .filter(|word| !word.starts_with('+'))
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+
+        #[tokio::test]
+        async fn test() {
+            let recorder = Recorder::new(10);
+            let snapshot = AsyncSnapshot::new(0);
+            snapshot.set_recorder(recorder.clone()).await;
+            for i in 1..=3 {
+                snapshot.update(i).await;
+            }
+
+            let mut time_travel = TimeTravel::new(recorder, |state: &i32| {
+                vec![StyledGraphemes::from(state.to_string())]
+            });
+            assert_eq!(header(&mut time_travel, 0, 0), "4/4 v3 (live)");
+            assert_eq!(header(&mut time_travel, 2, 0), "2/4 v1");
+            assert_eq!(header(&mut time_travel, 5, 0), "1/4 v0");
+
+            snapshot.update(4).await;
+            assert_eq!(header(&mut time_travel, 0, 1), "2/5 v1");
+            assert_eq!(header(&mut time_travel, 0, 9), "5/5 v4 (live)");
+        }
+    }
+
+    mod run {
+        use super::*;
+
+        use tokio::sync::mpsc;
+
+        #[tokio::test]
+        async fn test_renders_new_records() {
+            let recorder = Recorder::new(10);
+            let snapshot = AsyncSnapshot::new(0);
+            snapshot.set_recorder(recorder.clone()).await;
+            let mut time_travel = TimeTravel::new(recorder, |state: &i32| {
+                vec![StyledGraphemes::from(state.to_string())]
+            });
+
+            let (_events_tx, events_rx) = mpsc::channel(1);
+            let (pane_tx, mut pane_rx) = mpsc::channel(1);
+            let handle =
+                tokio::spawn(async move { time_travel.run((80, 10), events_rx, pane_tx).await });
+
+            let pane = pane_rx.recv().await.unwrap();
+            assert_eq!(pane.extract(3)[2].to_string(), "0");
+
+            snapshot.update(1).await;
+            let pane = pane_rx.recv().await.unwrap();
+            assert_eq!(pane.extract(3)[2].to_string(), "1");
+
+            handle.abort();
+        }
+    }
+}
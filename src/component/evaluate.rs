@@ -1,69 +1,346 @@
 use async_trait::async_trait;
-use promkit::pane::Pane;
+use futures::{stream::BoxStream, StreamExt};
+use promkit::{grapheme::StyledGraphemes, pane::Pane};
 use std::collections::VecDeque;
+use std::pin::Pin;
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, Mutex};
 use tokio::{sync::mpsc, task::JoinHandle};
+use tokio_util::sync::CancellationToken;
 
-use crate::Event;
+use crate::{operator::TimeBasedOperator, Event};
 
-#[derive(Clone, PartialEq)]
-enum State {
+mod cache;
+pub use cache::QueryCache;
+mod loading;
+pub use loading::{LoadingIndicator, LoadingPosition};
+mod monitor;
+pub use monitor::Monitor;
+mod parallel;
+mod progress;
+pub use progress::{Progress, ProgressReporter};
+
+/// What an evaluator is currently doing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum State {
+    #[default]
     Idle,
     ProcessQuery,
     ProcessEvents,
 }
 
+/// Snapshot of an evaluator's activity, published through `Monitor::status`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Status {
+    pub state: State,
+    /// The query most recently started.
+    pub query: Option<String>,
+    /// When the evaluator left `State::Idle`, if it is busy.
+    pub started_at: Option<Instant>,
+    /// Number of event groups waiting to be evaluated.
+    pub queue_len: usize,
+    pub generation: u64,
+}
+
 struct LoadingState {
     frame_index: usize,
     state: State,
+    started_at: Instant,
+    /// The last pane produced by an evaluation, kept to draw the indicator onto.
+    last_pane: Option<Pane>,
+    /// Whether the indicator has been drawn since the evaluation started.
+    shown: bool,
+    /// Incremented whenever in-flight evaluations are superseded;
+    /// panes from older generations are never rendered.
+    generation: u64,
+}
+
+impl LoadingState {
+    fn supersede(&mut self) -> u64 {
+        self.generation += 1;
+        self.generation
+    }
+
+    fn start(&mut self, state: State) {
+        if self.state == State::Idle || state == State::ProcessQuery {
+            self.started_at = Instant::now();
+        }
+        self.state = state;
+    }
+}
+
+/// What to do with event groups that arrive while a query is being processed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PendingEventsPolicy {
+    /// Discard the events.
+    Discard,
+    /// Queue the events and process them once the query completes.
+    /// Queued events are kept when a newer query supersedes the query,
+    /// and processed once the newer one completes.
+    #[default]
+    Buffer,
+    /// Process the events right away against the state preceding the query,
+    /// without waiting for the query to complete.
+    /// A newer query does not cancel them, but their panes are no longer rendered.
+    Optimistic,
+}
+
+/// When queries received by `Evaluator::run` are evaluated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QueryPolicy {
+    /// How long the query must stay unchanged before it is evaluated.
+    pub debounce: Duration,
+    /// Whether to skip a query equal to the one evaluated last.
+    pub deduplicate: bool,
+    /// Whether to let an in-flight query finish when the new query only extends it,
+    /// evaluating the new query afterwards instead of cancelling the in-flight one.
+    pub finish_extended: bool,
+}
+
+impl Default for QueryPolicy {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl QueryPolicy {
+    pub const DEFAULT: Self = Self {
+        debounce: Duration::ZERO,
+        deduplicate: true,
+        finish_extended: false,
+    };
+}
+
+/// What `Evaluator::run` does after an evaluation panics.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PanicPolicy {
+    /// Keep evaluating later queries and event groups.
+    #[default]
+    Restart,
+    /// Keep evaluating until more than the given number of panics occurred.
+    RestartUpTo(usize),
+    /// Stop evaluating; `run` returns.
+    /// This drops the event receiver, so the prompt's next dispatch to the component fails
+    /// and `Prompt::run` returns with an error, ending the whole prompt.
+    Stop,
+}
+
+/// Passed to each evaluation so that it can observe being superseded
+/// and report its progress.
+#[derive(Clone)]
+pub struct Context {
+    token: CancellationToken,
+    progress: ProgressReporter,
+    generation: u64,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Context {
+    /// Creates a context that nothing cancels and whose progress nobody observes,
+    /// e.g. to call an evaluator's methods directly in tests.
+    pub fn new() -> Self {
+        Self::with_progress(watch::Sender::new(Progress::default()), 0)
+    }
+
+    fn with_progress(progress: watch::Sender<Progress>, generation: u64) -> Self {
+        let token = CancellationToken::new();
+        Self {
+            progress: ProgressReporter::new(progress, token.clone()),
+            token,
+            generation,
+        }
+    }
+
+    /// Identifies the query this evaluation belongs to.
+    /// It increases every time a new query supersedes the previous one.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn progress(&self) -> &ProgressReporter {
+        &self.progress
+    }
+
+    /// Returns true once a newer query or event group has superseded this evaluation.
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Completes once this evaluation has been superseded.
+    pub async fn cancelled(&self) {
+        self.token.cancelled().await
+    }
+}
+
+/// What an evaluation was started for.
+enum Input {
+    Query(String),
+    Events(Vec<Event>),
+}
+
+type Task = JoinHandle<Result<(), mpsc::error::SendError<Pane>>>;
+
+struct Evaluation {
+    handle: Task,
+    context: Context,
+}
+
+impl Evaluation {
+    /// Signals cancellation and gives the task `grace` to finish by itself
+    /// before aborting it. `on_cancel` runs once the task has stopped.
+    fn cancel<E: Evaluator>(self, mut evaluator: E, grace: Duration) {
+        if self.handle.is_finished() {
+            return;
+        }
+        let Self {
+            mut handle,
+            context,
+        } = self;
+        context.token.cancel();
+        tokio::spawn(async move {
+            if tokio::time::timeout(grace, &mut handle).await.is_err() {
+                handle.abort();
+                let _ = handle.await;
+            }
+            evaluator.on_cancel(&context).await;
+        });
+    }
 }
 
 #[async_trait]
 pub trait Evaluator: Clone + Send + Sync + 'static {
-    const LOADING_FRAMES: [&'static str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+    const LOADING_INDICATOR: LoadingIndicator = LoadingIndicator::DEFAULT;
+    const QUERY_POLICY: QueryPolicy = QueryPolicy::DEFAULT;
+    const PENDING_EVENTS_POLICY: PendingEventsPolicy = PendingEventsPolicy::Buffer;
+    /// How long a cancelled evaluation may keep running
+    /// to clean up after itself before it is aborted.
+    const CANCEL_GRACE_PERIOD: Duration = Duration::from_millis(500);
+    /// How long a query may be evaluated before it is cancelled
+    /// and `timeout_pane` is rendered instead.
+    const QUERY_DEADLINE: Option<Duration> = None;
+    /// How long an event group may be evaluated before it is cancelled
+    /// and `timeout_pane` is rendered instead.
+    const EVENTS_DEADLINE: Option<Duration> = None;
+    const PANIC_POLICY: PanicPolicy = PanicPolicy::Restart;
+
+    async fn process_query(&mut self, area: (u16, u16), query: String, ctx: Context) -> Pane;
+    async fn process_events(&mut self, area: (u16, u16), events: Vec<Event>, ctx: Context) -> Pane;
+
+    /// Streams panes for `query` as partial results become available,
+    /// each one replacing the previous one on screen.
+    /// Once a newer query or event group supersedes it, no more of its panes are rendered
+    /// and the stream is dropped, at the latest after `CANCEL_GRACE_PERIOD`.
+    /// Defaults to the single pane returned by `process_query`.
+    fn stream_query(
+        &mut self,
+        area: (u16, u16),
+        query: String,
+        ctx: Context,
+    ) -> BoxStream<'static, Pane> {
+        let mut this = self.clone();
+        futures::stream::once(async move { this.process_query(area, query, ctx).await }).boxed()
+    }
+
+    /// Opts into memoizing query results.
+    /// Queries found in the returned cache are rendered without being evaluated,
+    /// and the final pane of every completed query is stored in it.
+    fn query_cache(&self) -> Option<&QueryCache> {
+        None
+    }
+
+    /// Renders an evaluation that exceeded its deadline.
+    /// Defaults to the last pane followed by a warning row.
+    /// The evaluation can be started again with `Monitor::retry`.
+    fn timeout_pane(&self, area: (u16, u16), last_pane: Option<&Pane>, timeout: Duration) -> Pane {
+        with_status_row(
+            area,
+            last_pane,
+            format!("⚠ timed out after {:.1}s", timeout.as_secs_f64()),
+        )
+    }
+
+    /// Renders an evaluation that panicked with `message`.
+    /// Defaults to the last pane followed by an error row.
+    fn panic_pane(&self, area: (u16, u16), last_pane: Option<&Pane>, message: &str) -> Pane {
+        with_status_row(area, last_pane, format!("✗ {}", message))
+    }
+
+    /// Called after an evaluation panicked, before `PANIC_POLICY` is applied,
+    /// e.g. to reset state the panic may have left inconsistent.
+    async fn on_panic(&mut self, _message: &str) {}
 
-    async fn process_query(&mut self, area: (u16, u16), query: String) -> Pane;
-    async fn process_events(&mut self, area: (u16, u16), events: Vec<Event>) -> Pane;
+    /// Called after a cancelled evaluation has stopped,
+    /// either by returning within the grace period or by being aborted.
+    /// `ctx` is the context the cancelled evaluation was given.
+    async fn on_cancel(&mut self, _ctx: &Context) {}
 
     async fn run(
+        &mut self,
+        area: (u16, u16),
+        query_rx: mpsc::Receiver<String>,
+        events_rx: mpsc::Receiver<Vec<Event>>,
+        tx: mpsc::Sender<Pane>,
+    ) {
+        self.run_monitored(area, query_rx, events_rx, tx, Monitor::new())
+            .await
+    }
+
+    /// Same as `run`, publishing the evaluator's activity to `monitor`.
+    async fn run_monitored(
         &mut self,
         area: (u16, u16),
         mut query_rx: mpsc::Receiver<String>,
         mut events_rx: mpsc::Receiver<Vec<Event>>,
         tx: mpsc::Sender<Pane>,
+        monitor: Monitor,
     ) {
-        let mut current_task: Option<JoinHandle<Result<(), mpsc::error::SendError<Pane>>>> = None;
+        let mut current_task: Option<Evaluation> = None;
+        let mut optimistic_tasks: Vec<Evaluation> = Vec::new();
         let loading_state = Arc::new(Mutex::new(LoadingState {
             frame_index: 0,
             state: State::Idle,
+            started_at: Instant::now(),
+            last_pane: None,
+            shown: false,
+            generation: 0,
         }));
         let mut event_queue: VecDeque<Vec<Event>> = VecDeque::new();
 
         let loading_task = {
             let loading_state = loading_state.clone();
+            let progress = monitor.progress();
             let tx = tx.clone();
             tokio::spawn(async move {
-                let mut interval = tokio::time::interval(Duration::from_millis(100));
+                let indicator = Self::LOADING_INDICATOR;
+                let mut interval = tokio::time::interval(indicator.tick());
                 loop {
                     interval.tick().await;
 
                     let mut state = loading_state.lock().await;
-                    if state.state == State::Idle {
+                    let elapsed = state.started_at.elapsed();
+                    if state.state == State::Idle || elapsed < indicator.delay {
                         continue;
                     }
 
                     let frame_index = state.frame_index;
-                    state.frame_index = (state.frame_index + 1) % Self::LOADING_FRAMES.len();
-                    drop(state);
-
-                    let loading_pane = Pane::new(
-                        vec![promkit::grapheme::StyledGraphemes::from(
-                            Self::LOADING_FRAMES[frame_index],
-                        )],
-                        0,
+                    state.frame_index = (state.frame_index + 1) % indicator.frames.len().max(1);
+                    state.shown = true;
+
+                    let loading_pane = indicator.overlay(
+                        state.last_pane.as_ref(),
+                        area,
+                        frame_index,
+                        elapsed,
+                        &progress.borrow(),
                     );
+                    // Sent while holding the lock so that it cannot land
+                    // after the pane restored once the evaluator becomes idle.
                     if tx.send(loading_pane).await.is_err() {
                         break;
                     }
@@ -71,108 +348,1121 @@ pub trait Evaluator: Clone + Send + Sync + 'static {
             })
         };
 
+        let policy = Self::QUERY_POLICY;
+        // The query most recently started (or served from the cache).
+        let mut last_query: Option<String> = None;
+        // The latest query received but not started yet.
+        let mut pending_query: Option<String> = None;
+        let mut debounce: Option<Pin<Box<tokio::time::Sleep>>> = None;
+        // When the current evaluation times out, after how long, and what it evaluates.
+        let mut deadline: Option<(Pin<Box<tokio::time::Sleep>>, Duration, Input)> = None;
+        // The evaluation that timed out last, started again by `Monitor::retry`.
+        let mut timed_out: Option<Input> = None;
+        // A query to evaluate again even if it equals the last one.
+        let mut retry_query: Option<String> = None;
+        // Whether to evaluate the last query again once idle.
+        let mut refresh = false;
+        // Whether the last query timed out or panicked,
+        // so that the same query may be evaluated again.
+        let mut last_query_failed = false;
+        let mut query_closed = false;
+        let mut panics = 0;
+        // The message of a panicked evaluation,
+        // and whether it was the current one rather than an optimistic one.
+        let mut panicked: Option<(String, bool)> = None;
+
         loop {
             tokio::select! {
-                Some(query) = query_rx.recv() => {
-                    if let Some(task) = current_task.take() {
-                        task.abort();
+                query = query_rx.recv(), if !query_closed => match query {
+                    Some(query) => {
+                        pending_query = Some(query);
+                        if !policy.debounce.is_zero() {
+                            debounce = Some(Box::pin(tokio::time::sleep(policy.debounce)));
+                        }
                     }
-
-                    event_queue.clear();
-
-                    let query = query.clone();
-                    let tx_clone = tx.clone();
-                    let loading_state = loading_state.clone();
-
-                    let process_task = {
-                        let mut this = self.clone();
-                        tokio::spawn(async move {
-                            {
-                                let mut state = loading_state.lock().await;
-                                state.state = State::ProcessQuery;
-                            }
-                            let result = this.process_query(area, query).await;
-                            {
-                                let mut state = loading_state.lock().await;
-                                state.state = State::Idle;
-                            }
-                            tx_clone.send(result).await
-                        })
-                    };
-
-                    current_task = Some(process_task);
+                    None => query_closed = true,
+                },
+                _ = async { debounce.as_mut().unwrap().await }, if debounce.is_some() => {
+                    debounce = None;
+                }
+                _ = monitor.retry.notified(), if !query_closed => match timed_out.take() {
+                    Some(Input::Events(events)) => event_queue.push_front(events),
+                    Some(Input::Query(query)) => retry_query = Some(query),
+                    None => retry_query = last_query.clone(),
+                },
+                _ = monitor.refresh.notified(), if !query_closed => {
+                    refresh = true;
                 }
                 Some(events) = events_rx.recv() => {
-                    let state = loading_state.lock().await.state.clone();
+                    let state = loading_state.lock().await.state;
 
-                    match state {
-                        State::ProcessQuery => {
-                            continue;
+                    match (state, Self::PENDING_EVENTS_POLICY) {
+                        (State::ProcessQuery, PendingEventsPolicy::Discard) => {}
+                        (State::ProcessQuery, PendingEventsPolicy::Optimistic) => {
+                            let generation = loading_state.lock().await.generation;
+                            // Reported apart from the query's progress, which the indicator shows.
+                            let context = Context::with_progress(
+                                watch::Sender::new(Progress::default()),
+                                generation,
+                            );
+                            optimistic_tasks.push(spawn_process_events(
+                                self.clone(),
+                                area,
+                                events,
+                                context,
+                                loading_state.clone(),
+                                tx.clone(),
+                            ));
                         }
-                        State::ProcessEvents => {
+                        _ => {
+                            // Started below once no other evaluation is running.
                             event_queue.push_back(events);
                         }
-                        State::Idle => {
-                            let events = events.clone();
-                            let tx_clone = tx.clone();
-                            let loading_state = loading_state.clone();
-
-                            let process_task = {
-                                let mut this = self.clone();
-                                tokio::spawn(async move {
-                                    {
-                                        let mut state = loading_state.lock().await;
-                                        state.state = State::ProcessEvents;
-                                    }
-                                    let result = this.process_events(area, events).await;
-                                    {
-                                        let mut state = loading_state.lock().await;
-                                        state.state = State::Idle;
-                                    }
-                                    tx_clone.send(result).await
-                                })
-                            };
-
-                            if let Some(task) = current_task.take() {
-                                task.abort();
-                            }
-                            current_task = Some(process_task);
+                    }
+                }
+                result = async { (&mut current_task.as_mut().unwrap().handle).await }, if current_task.is_some() => {
+                    current_task = None;
+                    deadline = None;
+
+                    if let Err(e) = result {
+                        if e.is_panic() {
+                            panicked = Some((panic_message(e.into_panic()), true));
+                        }
+                    }
+                }
+                (result, index, _) = async {
+                    let handles = optimistic_tasks.iter_mut().map(|task| &mut task.handle);
+                    futures::future::select_all(handles).await
+                }, if !optimistic_tasks.is_empty() => {
+                    optimistic_tasks.swap_remove(index);
+
+                    if let Err(e) = result {
+                        if e.is_panic() {
+                            panicked = Some((panic_message(e.into_panic()), false));
                         }
                     }
                 }
+                _ = async { deadline.as_mut().unwrap().0.as_mut().await }, if deadline.is_some() => {
+                    let (_, timeout, input) = deadline.take().unwrap();
+                    if let Some(task) = current_task.take() {
+                        task.cancel(self.clone(), Self::CANCEL_GRACE_PERIOD);
+                    }
+                    // Let the same query be evaluated again.
+                    last_query_failed |= matches!(input, Input::Query(_));
+                    timed_out = Some(input);
+
+                    let mut state = loading_state.lock().await;
+                    state.supersede();
+                    let pane = self.timeout_pane(area, state.last_pane.as_ref(), timeout);
+                    state.state = State::Idle;
+                    state.shown = false;
+                    let _ = tx.send(pane).await;
+                }
                 else => {
-                    loading_task.abort();
                     break;
                 }
             }
 
-            if let Some(events) = event_queue.pop_front() {
-                if loading_state.lock().await.state == State::Idle {
-                    let tx_clone = tx.clone();
-                    let loading_state = loading_state.clone();
-
-                    let process_task = {
-                        let mut this = self.clone();
-                        tokio::spawn(async move {
-                            {
-                                let mut state = loading_state.lock().await;
-                                state.state = State::ProcessEvents;
-                            }
-                            let result = this.process_events(area, events).await;
-                            {
-                                let mut state = loading_state.lock().await;
-                                state.state = State::Idle;
-                            }
-                            tx_clone.send(result).await
-                        })
-                    };
+            if let Some((message, current)) = panicked.take() {
+                self.on_panic(&message).await;
+                panics += 1;
 
-                    if let Some(task) = current_task.take() {
-                        task.abort();
+                {
+                    let mut state = loading_state.lock().await;
+                    let pane = self.panic_pane(area, state.last_pane.as_ref(), &message);
+                    if current {
+                        // Let the same query be evaluated again.
+                        last_query_failed |= state.state == State::ProcessQuery;
+                        state.state = State::Idle;
+                        state.shown = false;
+                    }
+                    let _ = tx.send(pane).await;
+                }
+
+                let stop = match Self::PANIC_POLICY {
+                    PanicPolicy::Restart => false,
+                    PanicPolicy::RestartUpTo(max) => panics > max,
+                    PanicPolicy::Stop => true,
+                };
+                if stop {
+                    break;
+                }
+            }
+
+            'query: {
+                if debounce.is_some() {
+                    break 'query;
+                }
+                // A newer query supersedes the one to retry.
+                let (query, retried) = match (pending_query.take(), retry_query.take()) {
+                    (Some(query), _) => (query, false),
+                    (None, Some(query)) => (query, true),
+                    (None, None) if refresh && current_task.is_none() && event_queue.is_empty() => {
+                        refresh = false;
+                        match last_query.clone() {
+                            Some(query) => (query, true),
+                            None => break 'query,
+                        }
+                    }
+                    (None, None) => break 'query,
+                };
+
+                if !retried
+                    && policy.deduplicate
+                    && !last_query_failed
+                    && last_query.as_ref() == Some(&query)
+                {
+                    break 'query;
+                }
+                if !retried
+                    && policy.finish_extended
+                    && current_task.is_some()
+                    && loading_state.lock().await.state == State::ProcessQuery
+                    && last_query
+                        .as_ref()
+                        .is_some_and(|last| query.starts_with(last.as_str()))
+                {
+                    pending_query = Some(query);
+                    break 'query;
+                }
+
+                if current_task.is_some()
+                    && loading_state.lock().await.state == State::ProcessEvents
+                {
+                    // Cancelling the events would lose them; the query starts once they are done.
+                    if retried {
+                        retry_query = Some(query);
+                    } else {
+                        pending_query = Some(query);
                     }
-                    current_task = Some(process_task);
+                    break 'query;
+                }
+
+                if let Some(task) = current_task.take() {
+                    task.cancel(self.clone(), Self::CANCEL_GRACE_PERIOD);
+                }
+                deadline = None;
+
+                last_query = Some(query.clone());
+                last_query_failed = false;
+                refresh = false;
+                if matches!(timed_out, Some(Input::Query(_))) {
+                    timed_out = None;
+                }
+
+                let generation = loading_state.lock().await.supersede();
+
+                // Retried and refreshed queries are evaluated rather than served from the cache.
+                let cached = self
+                    .query_cache()
+                    .filter(|_| !retried)
+                    .and_then(|cache| cache.get(&query, area));
+                if let Some(pane) = cached {
+                    let mut state = loading_state.lock().await;
+                    state.state = State::Idle;
+                    state.shown = false;
+                    state.last_pane = Some(pane.clone());
+                    let _ = tx.send(pane).await;
+                    break 'query;
+                }
+
+                loading_state.lock().await.start(State::ProcessQuery);
+                deadline = Self::QUERY_DEADLINE.map(|timeout| {
+                    (
+                        Box::pin(tokio::time::sleep(timeout)),
+                        timeout,
+                        Input::Query(query.clone()),
+                    )
+                });
+                current_task = Some(spawn_process_query(
+                    self.clone(),
+                    area,
+                    query,
+                    monitor.start_evaluation(generation),
+                    loading_state.clone(),
+                    tx.clone(),
+                ));
+            }
+
+            if current_task.is_none() && !event_queue.is_empty() {
+                // Evaluate all queued groups at once rather than one after another.
+                let events = TimeBasedOperator::merge_event_groups(event_queue.drain(..));
+                deadline = Self::EVENTS_DEADLINE.map(|timeout| {
+                    (
+                        Box::pin(tokio::time::sleep(timeout)),
+                        timeout,
+                        Input::Events(events.clone()),
+                    )
+                });
+                let generation = {
+                    let mut state = loading_state.lock().await;
+                    state.start(State::ProcessEvents);
+                    state.generation
+                };
+                current_task = Some(spawn_process_events(
+                    self.clone(),
+                    area,
+                    events,
+                    monitor.start_evaluation(generation),
+                    loading_state.clone(),
+                    tx.clone(),
+                ));
+            } else if current_task.is_none() {
+                let mut state = loading_state.lock().await;
+                if state.state != State::Idle {
+                    state.state = State::Idle;
+                    if std::mem::take(&mut state.shown) {
+                        if let Some(pane) = state.last_pane.clone() {
+                            let _ = tx.send(pane).await;
+                        }
+                    }
+                }
+            }
+
+            let status = {
+                let state = loading_state.lock().await;
+                Status {
+                    state: state.state,
+                    query: last_query.clone(),
+                    started_at: (state.state != State::Idle).then_some(state.started_at),
+                    queue_len: event_queue.len(),
+                    generation: state.generation,
+                }
+            };
+            monitor.publish(status);
+        }
+
+        if let Some(task) = current_task.take() {
+            task.cancel(self.clone(), Self::CANCEL_GRACE_PERIOD);
+        }
+        for task in optimistic_tasks {
+            task.cancel(self.clone(), Self::CANCEL_GRACE_PERIOD);
+        }
+        loading_task.abort();
+        monitor.publish(Status {
+            query: last_query,
+            ..Default::default()
+        });
+    }
+}
+
+/// Appends a row showing `status` below the visible rows of `last_pane`.
+fn with_status_row(area: (u16, u16), last_pane: Option<&Pane>, status: String) -> Pane {
+    let mut rows = last_pane
+        .map(|pane| pane.extract(area.1.saturating_sub(1) as usize))
+        .unwrap_or_default();
+    rows.push(StyledGraphemes::from(status));
+    Pane::new(rows, 0)
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&'static str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => String::from("evaluator panicked"),
+        },
+    }
+}
+
+/// Renders `pane` unless its `generation` has been superseded.
+/// The check and the send happen under the same lock as superseding,
+/// so that a stale pane can never be rendered after a newer one.
+/// Returns whether the pane was sent.
+async fn publish(
+    loading_state: &Mutex<LoadingState>,
+    generation: u64,
+    pane: Pane,
+    tx: &mpsc::Sender<Pane>,
+) -> Result<bool, mpsc::error::SendError<Pane>> {
+    let mut state = loading_state.lock().await;
+    if state.generation != generation {
+        return Ok(false);
+    }
+    state.last_pane = Some(pane.clone());
+    tx.send(pane).await?;
+    Ok(true)
+}
+
+fn spawn_process_query<E: Evaluator>(
+    mut evaluator: E,
+    area: (u16, u16),
+    query: String,
+    context: Context,
+    loading_state: Arc<Mutex<LoadingState>>,
+    tx: mpsc::Sender<Pane>,
+) -> Evaluation {
+    let generation = context.generation;
+    let handle = {
+        let context = context.clone();
+        tokio::spawn(async move {
+            let cache = evaluator.query_cache().cloned();
+            let mut panes = evaluator.stream_query(area, query.clone(), context.clone());
+            let mut last = None;
+            while let Some(result) = panes.next().await {
+                if context.is_cancelled() {
+                    return Ok(());
+                }
+                last = Some(result.clone());
+                if !publish(&loading_state, generation, result, &tx).await? {
+                    return Ok(());
                 }
             }
+            // A stream cut short by cancellation ends with a partial pane.
+            if context.is_cancelled() {
+                return Ok(());
+            }
+            if let (Some(cache), Some(pane)) = (cache, last) {
+                cache.insert(&query, area, pane);
+            }
+            Ok(())
+        })
+    };
+    Evaluation { handle, context }
+}
+
+fn spawn_process_events<E: Evaluator>(
+    mut evaluator: E,
+    area: (u16, u16),
+    events: Vec<Event>,
+    context: Context,
+    loading_state: Arc<Mutex<LoadingState>>,
+    tx: mpsc::Sender<Pane>,
+) -> Evaluation {
+    let generation = context.generation;
+    let handle = {
+        let context = context.clone();
+        tokio::spawn(async move {
+            let result = crate::snapshot::triggered_by(
+                events.clone(),
+                evaluator.process_events(area, events, context.clone()),
+            )
+            .await;
+            if context.is_cancelled() {
+                return Ok(());
+            }
+            publish(&loading_state, generation, result, &tx)
+                .await
+                .map(|_| ())
+        })
+    };
+    Evaluation { handle, context }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{
+        marker::PhantomData,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use promkit::grapheme::StyledGraphemes;
+
+    /// The policies a `Logger` evaluates with, defaulting to the evaluator's own.
+    trait Policies: Clone + Send + Sync + 'static {
+        const QUERY_POLICY: QueryPolicy = QueryPolicy::DEFAULT;
+        const PENDING_EVENTS_POLICY: PendingEventsPolicy = PendingEventsPolicy::Buffer;
+        const CANCEL_GRACE_PERIOD: Duration = Duration::from_millis(500);
+        const QUERY_DEADLINE: Option<Duration> = None;
+        const EVENTS_DEADLINE: Option<Duration> = None;
+    }
+
+    #[derive(Clone)]
+    struct Buffer;
+    impl Policies for Buffer {}
+
+    #[derive(Clone)]
+    struct Discard;
+    impl Policies for Discard {
+        const PENDING_EVENTS_POLICY: PendingEventsPolicy = PendingEventsPolicy::Discard;
+    }
+
+    #[derive(Clone)]
+    struct Optimistic;
+    impl Policies for Optimistic {
+        const PENDING_EVENTS_POLICY: PendingEventsPolicy = PendingEventsPolicy::Optimistic;
+    }
+
+    /// Debounces queries and lets extended queries finish.
+    #[derive(Clone)]
+    struct Debounce;
+    impl Policies for Debounce {
+        const QUERY_POLICY: QueryPolicy = QueryPolicy {
+            debounce: Duration::from_millis(300),
+            finish_extended: true,
+            ..QueryPolicy::DEFAULT
+        };
+    }
+
+    /// Gives up on queries after one second.
+    #[derive(Clone)]
+    struct QueryDeadline;
+    impl Policies for QueryDeadline {
+        const QUERY_DEADLINE: Option<Duration> = Some(Duration::from_secs(1));
+    }
+
+    /// Gives up on event groups after one second.
+    #[derive(Clone)]
+    struct EventsDeadline;
+    impl Policies for EventsDeadline {
+        const EVENTS_DEADLINE: Option<Duration> = Some(Duration::from_secs(1));
+    }
+
+    /// Waits a minute for cancelled evaluations to finish.
+    #[derive(Clone)]
+    struct Patient;
+    impl Policies for Patient {
+        const CANCEL_GRACE_PERIOD: Duration = Duration::from_secs(60);
+    }
+
+    /// Logs every evaluation and renders it as a single line,
+    /// taking `delay` for each query.
+    #[derive(Clone)]
+    struct Logger<P: Policies = Buffer> {
+        log: Arc<std::sync::Mutex<Vec<String>>>,
+        delay: Duration,
+        lagging: bool,
+        stalled_events: Arc<AtomicUsize>,
+        cache: Option<QueryCache>,
+        policies: PhantomData<P>,
+    }
+
+    impl<P: Policies> Logger<P> {
+        fn new(delay: Duration) -> Self {
+            Self {
+                log: Default::default(),
+                delay,
+                lagging: false,
+                stalled_events: Default::default(),
+                cache: None,
+                policies: PhantomData,
+            }
+        }
+
+        /// Ignores cancellation, and takes `delay` per character of the query.
+        fn lagging(mut self) -> Self {
+            self.lagging = true;
+            self
+        }
+
+        /// Leaves the first `count` event groups unfinished until they are cancelled.
+        fn stall_events(self, count: usize) -> Self {
+            self.stalled_events.store(count, Ordering::SeqCst);
+            self
+        }
+
+        fn with_cache(mut self, cache: QueryCache) -> Self {
+            self.cache = Some(cache);
+            self
+        }
+
+        fn log(&self) -> Vec<String> {
+            self.log.lock().unwrap().clone()
+        }
+
+        fn record(&self, line: String) -> Pane {
+            self.log.lock().unwrap().push(line.clone());
+            Pane::new(vec![StyledGraphemes::from(line)], 0)
+        }
+    }
+
+    #[async_trait]
+    impl<P: Policies> Evaluator for Logger<P> {
+        const QUERY_POLICY: QueryPolicy = P::QUERY_POLICY;
+        const PENDING_EVENTS_POLICY: PendingEventsPolicy = P::PENDING_EVENTS_POLICY;
+        const CANCEL_GRACE_PERIOD: Duration = P::CANCEL_GRACE_PERIOD;
+        const QUERY_DEADLINE: Option<Duration> = P::QUERY_DEADLINE;
+        const EVENTS_DEADLINE: Option<Duration> = P::EVENTS_DEADLINE;
+
+        async fn process_query(&mut self, _area: (u16, u16), query: String, ctx: Context) -> Pane {
+            ctx.progress().set_status(format!("query:{}", query));
+            if self.lagging {
+                tokio::time::sleep(self.delay * query.len() as u32).await;
+                return self.record(format!("query:{}", query));
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(self.delay) => self.record(format!("query:{}", query)),
+                _ = ctx.cancelled() => self.record(format!("cancelled:{}", query)),
+            }
+        }
+
+        async fn process_events(
+            &mut self,
+            _area: (u16, u16),
+            events: Vec<Event>,
+            ctx: Context,
+        ) -> Pane {
+            let stalled = self
+                .stalled_events
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+            if stalled {
+                ctx.cancelled().await;
+                return self.record(format!("cancelled:{:?}", events));
+            }
+            self.record(format!("events:{:?}", events))
+        }
+
+        fn query_cache(&self) -> Option<&QueryCache> {
+            self.cache.as_ref()
+        }
+
+        async fn on_cancel(&mut self, ctx: &Context) {
+            self.record(format!("on_cancel:{}", ctx.generation()));
+        }
+    }
+
+    /// An evaluator running in the background on a 10x10 area.
+    struct Running {
+        query_tx: mpsc::Sender<String>,
+        events_tx: mpsc::Sender<Vec<Event>>,
+        handle: JoinHandle<Vec<Pane>>,
+    }
+
+    impl Running {
+        async fn query(&self, query: &str) {
+            self.query_tx.send(query.to_string()).await.unwrap();
+        }
+
+        async fn events(&self, events: Vec<Event>) {
+            self.events_tx.send(events).await.unwrap();
+        }
+
+        /// Closes the inputs and returns every pane the evaluator sent.
+        async fn finish(self) -> Vec<Pane> {
+            drop(self.query_tx);
+            drop(self.events_tx);
+            self.handle.await.unwrap()
+        }
+    }
+
+    fn start<E: Evaluator>(mut evaluator: E, monitor: Monitor) -> Running {
+        let (query_tx, query_rx) = mpsc::channel(1);
+        let (events_tx, events_rx) = mpsc::channel(1);
+        let (pane_tx, mut pane_rx) = mpsc::channel(1);
+
+        let handle = tokio::spawn(async move {
+            let collect = async {
+                let mut panes = Vec::new();
+                while let Some(pane) = pane_rx.recv().await {
+                    panes.push(pane);
+                }
+                panes
+            };
+            let run = evaluator.run_monitored((10, 10), query_rx, events_rx, pane_tx, monitor);
+            tokio::join!(run, collect).1
+        });
+        Running {
+            query_tx,
+            events_tx,
+            handle,
+        }
+    }
+
+    /// The first row of each pane.
+    fn first_rows(panes: &[Pane]) -> Vec<String> {
+        panes
+            .iter()
+            .map(|pane| pane.extract(1)[0].to_string())
+            .collect()
+    }
+
+    async fn interleave<P: Policies>(evaluator: Logger<P>) -> Vec<String> {
+        let running = start(evaluator.clone(), Monitor::new());
+
+        running.query("a").await;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        running
+            .events(vec![Event::HorizontalCursorBuffer(1, 0)])
+            .await;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        running
+            .events(vec![Event::HorizontalCursorBuffer(0, 1)])
+            .await;
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        running.finish().await;
+
+        evaluator.log()
+    }
+
+    async fn render_queries<E: Evaluator>(evaluator: E, queries: &[&str]) -> Vec<String> {
+        let running = start(evaluator, Monitor::new());
+
+        for query in queries {
+            if running.query_tx.send(query.to_string()).await.is_err() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+
+        running
+            .finish()
+            .await
+            .iter()
+            .map(|pane| {
+                pane.extract(10)
+                    .iter()
+                    .map(|row| row.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .collect()
+    }
+
+    mod run {
+        use super::*;
+
+        #[tokio::test(start_paused = true)]
+        async fn test_discard_events_during_query() {
+            let log = interleave(Logger::<Discard>::new(Duration::from_secs(5))).await;
+
+            assert_eq!(log, vec!["query:a"]);
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_buffer_events_during_query() {
+            let log = interleave(Logger::<Buffer>::new(Duration::from_secs(5))).await;
+
+            assert_eq!(
+                log,
+                vec!["query:a", "events:[HorizontalCursorBuffer(1, 1)]"]
+            );
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_apply_events_optimistically_during_query() {
+            let log = interleave(Logger::<Optimistic>::new(Duration::from_secs(5))).await;
+
+            assert_eq!(
+                log,
+                vec![
+                    "events:[HorizontalCursorBuffer(1, 0)]",
+                    "events:[HorizontalCursorBuffer(0, 1)]",
+                    "query:a",
+                ]
+            );
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_new_query_keeps_buffered_events() {
+            let evaluator = Logger::<Buffer>::new(Duration::from_secs(5));
+            let running = start(evaluator.clone(), Monitor::new());
+
+            running.query("a").await;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            running
+                .events(vec![Event::HorizontalCursorBuffer(1, 0)])
+                .await;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            running.query("ab").await;
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            running.finish().await;
+
+            assert_eq!(
+                evaluator.log(),
+                vec![
+                    "cancelled:a",
+                    "on_cancel:1",
+                    "query:ab",
+                    "events:[HorizontalCursorBuffer(1, 0)]",
+                ]
+            );
+        }
+
+        /// Streams `query:<n>` once per second, three times, until cancelled.
+        /// Gives up on queries after `DEADLINE_MS` unless it is 0.
+        #[derive(Clone, Default)]
+        struct Streamer<const DEADLINE_MS: u64> {
+            cache: Option<QueryCache>,
+        }
+
+        #[async_trait]
+        impl<const DEADLINE_MS: u64> Evaluator for Streamer<DEADLINE_MS> {
+            const LOADING_INDICATOR: LoadingIndicator = LoadingIndicator {
+                delay: Duration::MAX,
+                ..LoadingIndicator::DEFAULT
+            };
+            const QUERY_DEADLINE: Option<Duration> = match DEADLINE_MS {
+                0 => None,
+                ms => Some(Duration::from_millis(ms)),
+            };
+
+            async fn process_query(&mut self, _: (u16, u16), _: String, _: Context) -> Pane {
+                unreachable!()
+            }
+
+            async fn process_events(&mut self, _: (u16, u16), _: Vec<Event>, _: Context) -> Pane {
+                unreachable!()
+            }
+
+            fn stream_query(
+                &mut self,
+                _area: (u16, u16),
+                query: String,
+                ctx: Context,
+            ) -> BoxStream<'static, Pane> {
+                futures::stream::iter(0..3)
+                    .then(move |n| {
+                        let query = query.clone();
+                        async move {
+                            tokio::time::sleep(Duration::from_secs(1)).await;
+                            Pane::new(vec![StyledGraphemes::from(format!("{}:{}", query, n))], 0)
+                        }
+                    })
+                    .take_until(async move { ctx.cancelled().await })
+                    .boxed()
+            }
+
+            fn query_cache(&self) -> Option<&QueryCache> {
+                self.cache.as_ref()
+            }
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_stream_query_until_superseded() {
+            let running = start(Streamer::<0>::default(), Monitor::new());
+
+            running.query("a").await;
+            tokio::time::sleep(Duration::from_millis(2500)).await;
+            running.query("b").await;
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            let panes = running.finish().await;
+
+            assert_eq!(first_rows(&panes), vec!["a:0", "a:1", "b:0", "b:1", "b:2"]);
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_cancelled_stream_is_not_cached() {
+            let cache = QueryCache::new(8);
+            let evaluator = Streamer::<1500> {
+                cache: Some(cache.clone()),
+            };
+            let running = start(evaluator, Monitor::new());
+
+            // Cut off by the deadline after "a:0".
+            running.query("a").await;
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            assert!(cache.get("a", (10, 10)).is_none());
+
+            running.finish().await;
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_repeated_query_is_served_from_cache() {
+            let evaluator =
+                Logger::<Buffer>::new(Duration::from_secs(5)).with_cache(QueryCache::new(8));
+            let running = start(evaluator.clone(), Monitor::new());
+
+            for query in ["a", "b", "a"] {
+                running.query(query).await;
+                tokio::time::sleep(Duration::from_secs(10)).await;
+            }
+            let panes = running.finish().await;
+
+            assert_eq!(evaluator.log(), vec!["query:a", "query:b"]);
+            assert_eq!(
+                first_rows(&panes).last().map(String::as_str),
+                Some("query:a")
+            );
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_debounce_and_deduplicate_queries() {
+            let evaluator = Logger::<Debounce>::new(Duration::from_secs(5));
+            let running = start(evaluator.clone(), Monitor::new());
+
+            // "a" is superseded within the debounce period.
+            running.query("a").await;
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            running.query("ab").await;
+            // "abc" extends the in-flight "ab", which is allowed to finish.
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            running.query("abc").await;
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            // "abc" again is a duplicate.
+            running.query("abc").await;
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            running.finish().await;
+
+            assert_eq!(evaluator.log(), vec!["query:ab", "query:abc"]);
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_query_deadline_and_retry() {
+            let evaluator = Logger::<QueryDeadline>::new(Duration::from_secs(5));
+            let monitor = Monitor::new();
+            let running = start(evaluator.clone(), monitor.clone());
+
+            running.query("a").await;
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            monitor.retry();
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            let panes = running.finish().await;
+
+            let timeouts = panes
+                .iter()
+                .filter(|pane| {
+                    pane.extract(10)
+                        .iter()
+                        .any(|row| row.to_string() == "⚠ timed out after 1.0s")
+                })
+                .count();
+            assert_eq!(timeouts, 2);
+            assert_eq!(
+                evaluator.log(),
+                vec!["cancelled:a", "on_cancel:1", "cancelled:a", "on_cancel:3"]
+            );
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_events_deadline_and_retry() {
+            let evaluator = Logger::<EventsDeadline>::new(Duration::ZERO).stall_events(1);
+            let monitor = Monitor::new();
+            let running = start(evaluator.clone(), monitor.clone());
+
+            running.query("a").await;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            running
+                .events(vec![Event::HorizontalCursorBuffer(1, 0)])
+                .await;
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            // Retries the event group rather than the query.
+            monitor.retry();
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            // An identical query is still deduplicated.
+            running.query("a").await;
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            running.finish().await;
+
+            assert_eq!(
+                evaluator.log(),
+                vec![
+                    "query:a",
+                    "cancelled:[HorizontalCursorBuffer(1, 0)]",
+                    "on_cancel:1",
+                    "events:[HorizontalCursorBuffer(1, 0)]",
+                ]
+            );
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_refresh_waits_for_evaluations() {
+            let evaluator = Logger::<Buffer>::new(Duration::from_secs(5));
+            let monitor = Monitor::new();
+            let running = start(evaluator.clone(), monitor.clone());
+
+            running.query("a").await;
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            monitor.refresh();
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            running
+                .events(vec![Event::HorizontalCursorBuffer(1, 0)])
+                .await;
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            running.finish().await;
+
+            assert_eq!(
+                evaluator.log(),
+                vec![
+                    "query:a",
+                    "events:[HorizontalCursorBuffer(1, 0)]",
+                    "query:a",
+                ]
+            );
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_stale_result_is_not_rendered() {
+            let evaluator = Logger::<Patient>::new(Duration::from_secs(1)).lagging();
+            let running = start(evaluator.clone(), Monitor::new());
+
+            // "slow" finishes 2 seconds after "b" has been rendered.
+            running.query("slow").await;
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            running.query("b").await;
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            let rendered = first_rows(&running.finish().await);
+
+            assert_eq!(
+                evaluator.log(),
+                vec!["query:b", "query:slow", "on_cancel:1"]
+            );
+            assert!(!rendered.iter().any(|row| row.contains("query:slow")));
+            assert_eq!(rendered.last().map(String::as_str), Some("query:b"));
+        }
+
+        /// Panics on the query "boom".
+        #[derive(Clone)]
+        struct Panicky<const STOP: bool>;
+
+        #[async_trait]
+        impl<const STOP: bool> Evaluator for Panicky<STOP> {
+            const LOADING_INDICATOR: LoadingIndicator = LoadingIndicator {
+                delay: Duration::MAX,
+                ..LoadingIndicator::DEFAULT
+            };
+            const PANIC_POLICY: PanicPolicy = if STOP {
+                PanicPolicy::Stop
+            } else {
+                PanicPolicy::Restart
+            };
+
+            async fn process_query(&mut self, _: (u16, u16), query: String, _: Context) -> Pane {
+                if query == "boom" {
+                    panic!("boom!");
+                }
+                Pane::new(vec![StyledGraphemes::from(format!("query:{}", query))], 0)
+            }
+
+            async fn process_events(&mut self, _: (u16, u16), _: Vec<Event>, _: Context) -> Pane {
+                unreachable!()
+            }
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_restart_after_panic() {
+            let rendered = render_queries(Panicky::<false>, &["a", "boom", "b"]).await;
+
+            assert_eq!(rendered, vec!["query:a", "query:a\n✗ boom!", "query:b"]);
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_stop_after_panic() {
+            let rendered = render_queries(Panicky::<true>, &["a", "boom", "b"]).await;
+
+            assert_eq!(rendered, vec!["query:a", "query:a\n✗ boom!"]);
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_optimistic_events_keep_query_progress() {
+            let monitor = Monitor::new();
+            let progress = monitor.progress();
+            let running = start(
+                Logger::<Optimistic>::new(Duration::from_secs(5)),
+                monitor.clone(),
+            );
+
+            running.query("a").await;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            running
+                .events(vec![Event::HorizontalCursorBuffer(1, 0)])
+                .await;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            assert_eq!(progress.borrow().status, "query:a");
+
+            running.finish().await;
+        }
+
+        /// Panics on every event group, evaluated optimistically during a slow query.
+        #[derive(Clone)]
+        struct PanickyEvents;
+
+        #[async_trait]
+        impl Evaluator for PanickyEvents {
+            const LOADING_INDICATOR: LoadingIndicator = LoadingIndicator {
+                delay: Duration::MAX,
+                ..LoadingIndicator::DEFAULT
+            };
+            const PENDING_EVENTS_POLICY: PendingEventsPolicy = PendingEventsPolicy::Optimistic;
+            const PANIC_POLICY: PanicPolicy = PanicPolicy::Stop;
+
+            async fn process_query(&mut self, _: (u16, u16), query: String, _: Context) -> Pane {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Pane::new(vec![StyledGraphemes::from(format!("query:{}", query))], 0)
+            }
+
+            async fn process_events(&mut self, _: (u16, u16), _: Vec<Event>, _: Context) -> Pane {
+                panic!("boom!");
+            }
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_stop_after_optimistic_panic() {
+            let running = start(PanickyEvents, Monitor::new());
+
+            running.query("a").await;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            running
+                .events(vec![Event::HorizontalCursorBuffer(1, 0)])
+                .await;
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            assert!(running.handle.is_finished());
+
+            assert_eq!(first_rows(&running.finish().await), vec!["✗ boom!"]);
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_status() {
+            let monitor = Monitor::new();
+            let status = monitor.status();
+            let running = start(
+                Logger::<Buffer>::new(Duration::from_secs(5)),
+                monitor.clone(),
+            );
+
+            running.query("a").await;
+            for _ in 0..2 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                running
+                    .events(vec![Event::HorizontalCursorBuffer(1, 0)])
+                    .await;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            {
+                let status = status.borrow();
+                assert_eq!(status.state, State::ProcessQuery);
+                assert_eq!(status.query.as_deref(), Some("a"));
+                assert!(status.started_at.is_some());
+                assert_eq!(status.queue_len, 2);
+            }
+
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            assert_eq!(
+                *status.borrow(),
+                Status {
+                    state: State::Idle,
+                    query: Some(String::from("a")),
+                    started_at: None,
+                    queue_len: 0,
+                    generation: 1,
+                }
+            );
+
+            running.finish().await;
+        }
+    }
+
+    mod publish {
+        use super::*;
+
+        #[tokio::test]
+        async fn test_drops_superseded_generation() {
+            let loading_state = Mutex::new(LoadingState {
+                frame_index: 0,
+                state: State::Idle,
+                started_at: Instant::now(),
+                last_pane: None,
+                shown: false,
+                generation: 0,
+            });
+            let (tx, mut rx) = mpsc::channel(4);
+            let pane = |text: &str| Pane::new(vec![StyledGraphemes::from(text)], 0);
+
+            let stale = loading_state.lock().await.generation;
+            let current = loading_state.lock().await.supersede();
+
+            assert!(publish(&loading_state, current, pane("new"), &tx)
+                .await
+                .unwrap());
+            assert!(!publish(&loading_state, stale, pane("old"), &tx)
+                .await
+                .unwrap());
+            drop(tx);
+
+            assert_eq!(rx.recv().await.unwrap().extract(1)[0].to_string(), "new");
+            assert!(rx.recv().await.is_none());
         }
     }
 }
@@ -0,0 +1,144 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use promkit::pane::Pane;
+
+type Key = (String, (u16, u16));
+
+/// Memoizes the panes produced for queries, keyed by query string and area,
+/// evicting the least recently used entries beyond `capacity`.
+/// Clones share the same entries.
+#[derive(Clone)]
+pub struct QueryCache {
+    inner: Arc<Mutex<QueryCacheInner>>,
+}
+
+struct QueryCacheInner {
+    capacity: usize,
+    entries: HashMap<Key, Pane>,
+    /// Keys from least to most recently used.
+    recency: VecDeque<Key>,
+}
+
+impl QueryCacheInner {
+    fn touch(&mut self, key: &Key) {
+        if let Some(pos) = self.recency.iter().position(|k| k == key) {
+            let key = self.recency.remove(pos).unwrap();
+            self.recency.push_back(key);
+        }
+    }
+}
+
+impl QueryCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(QueryCacheInner {
+                capacity,
+                entries: HashMap::new(),
+                recency: VecDeque::new(),
+            })),
+        }
+    }
+
+    pub fn get(&self, query: &str, area: (u16, u16)) -> Option<Pane> {
+        let mut inner = self.inner.lock().unwrap();
+        let key = (query.to_string(), area);
+        let pane = inner.entries.get(&key).cloned()?;
+        inner.touch(&key);
+        Some(pane)
+    }
+
+    pub fn insert(&self, query: &str, area: (u16, u16), pane: Pane) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.capacity == 0 {
+            return;
+        }
+        let key = (query.to_string(), area);
+        if inner.entries.insert(key.clone(), pane).is_some() {
+            inner.touch(&key);
+            return;
+        }
+        inner.recency.push_back(key);
+        while inner.entries.len() > inner.capacity {
+            if let Some(oldest) = inner.recency.pop_front() {
+                inner.entries.remove(&oldest);
+            }
+        }
+    }
+
+    /// Drops the entries for `query`, whatever their area.
+    pub fn invalidate(&self, query: &str) {
+        self.retain(|key| key != query);
+    }
+
+    /// Keeps only the entries whose query satisfies `f`.
+    pub fn retain<F: FnMut(&str) -> bool>(&self, mut f: F) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.recency.retain(|(query, _)| f(query));
+        let QueryCacheInner {
+            entries, recency, ..
+        } = &mut *inner;
+        entries.retain(|key, _| recency.contains(key));
+    }
+
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.entries.clear();
+        inner.recency.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use promkit::grapheme::StyledGraphemes;
+
+    fn pane(text: &str) -> Pane {
+        Pane::new(vec![StyledGraphemes::from(text)], 0)
+    }
+
+    mod insert {
+        use super::*;
+
+        #[test]
+        fn test_evicts_least_recently_used() {
+            let cache = QueryCache::new(2);
+            cache.insert("a", (10, 10), pane("a"));
+            cache.insert("b", (10, 10), pane("b"));
+            assert!(cache.get("a", (10, 10)).is_some());
+            cache.insert("c", (10, 10), pane("c"));
+
+            assert!(cache.get("a", (10, 10)).is_some());
+            assert!(cache.get("b", (10, 10)).is_none());
+            assert!(cache.get("c", (10, 10)).is_some());
+            assert!(cache.get("c", (20, 20)).is_none());
+        }
+    }
+
+    mod invalidate {
+        use super::*;
+
+        #[test]
+        fn test() {
+            let cache = QueryCache::new(4);
+            cache.insert("a", (10, 10), pane("a"));
+            cache.insert("a", (20, 20), pane("a"));
+            cache.insert("b", (10, 10), pane("b"));
+            cache.invalidate("a");
+
+            assert_eq!(cache.len(), 1);
+            assert!(cache.get("b", (10, 10)).is_some());
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use std::time::Duration;
+
+use promkit::{grapheme::StyledGraphemes, pane::Pane};
+
+use super::progress::Progress;
+
+/// Where the loading indicator is drawn relative to the last rendered pane.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LoadingPosition {
+    /// Before the first visible row.
+    Prefix,
+    /// After the last visible row.
+    #[default]
+    Suffix,
+    /// On its own row below the pane.
+    StatusLine,
+}
+
+/// Appearance and timing of the indicator shown while an evaluator is busy.
+#[derive(Clone, Copy, Debug)]
+pub struct LoadingIndicator {
+    /// Drawn one after another; without frames,
+    /// only the label, elapsed time and progress are shown.
+    pub frames: &'static [&'static str],
+    /// Time between two frames, at least `MIN_INTERVAL`.
+    pub interval: Duration,
+    /// Text shown next to the current frame.
+    pub label: &'static str,
+    /// Whether to show the time elapsed since the evaluation started.
+    pub show_elapsed: bool,
+    /// How long an evaluation must run before the indicator appears,
+    /// so that fast evaluations never flash it.
+    pub delay: Duration,
+    pub position: LoadingPosition,
+}
+
+impl Default for LoadingIndicator {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl LoadingIndicator {
+    pub const MIN_INTERVAL: Duration = Duration::from_millis(1);
+
+    pub const DEFAULT: Self = Self {
+        frames: &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"],
+        interval: Duration::from_millis(100),
+        label: "",
+        show_elapsed: false,
+        delay: Duration::from_millis(200),
+        position: LoadingPosition::Suffix,
+    };
+
+    /// Renders the indicator text for `frame_index` after `elapsed`.
+    pub fn text(&self, frame_index: usize, elapsed: Duration, progress: &Progress) -> String {
+        let mut parts = Vec::new();
+        if !self.frames.is_empty() {
+            parts.push(String::from(self.frames[frame_index % self.frames.len()]));
+        }
+        if !self.label.is_empty() {
+            parts.push(String::from(self.label));
+        }
+        if self.show_elapsed {
+            parts.push(format!("{:.1}s", elapsed.as_secs_f64()));
+        }
+        if !progress.is_empty() {
+            parts.push(progress.render());
+        }
+        parts.join(" ")
+    }
+
+    /// Time between two frames, clamped to `MIN_INTERVAL`.
+    pub(super) fn tick(&self) -> Duration {
+        self.interval.max(Self::MIN_INTERVAL)
+    }
+
+    /// Composites the indicator onto the visible rows of `pane`,
+    /// keeping its content visible.
+    pub fn overlay(
+        &self,
+        pane: Option<&Pane>,
+        area: (u16, u16),
+        frame_index: usize,
+        elapsed: Duration,
+        progress: &Progress,
+    ) -> Pane {
+        let text = self.text(frame_index, elapsed, progress);
+        let height = match self.position {
+            LoadingPosition::StatusLine => area.1.saturating_sub(1),
+            _ => area.1,
+        } as usize;
+        let mut rows = pane.map(|pane| pane.extract(height)).unwrap_or_default();
+
+        match self.position {
+            LoadingPosition::Prefix => match rows.first_mut() {
+                Some(row) => {
+                    *row = StyledGraphemes::from_iter([
+                        &StyledGraphemes::from(format!("{} ", text)),
+                        &*row,
+                    ]);
+                }
+                None => rows.push(StyledGraphemes::from(text)),
+            },
+            LoadingPosition::Suffix => match rows.last_mut() {
+                Some(row) => {
+                    *row = StyledGraphemes::from_iter([
+                        &*row,
+                        &StyledGraphemes::from(format!(" {}", text)),
+                    ]);
+                }
+                None => rows.push(StyledGraphemes::from(text)),
+            },
+            LoadingPosition::StatusLine => rows.push(StyledGraphemes::from(text)),
+        }
+
+        Pane::new(rows, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod overlay {
+        use super::*;
+
+        fn render(pane: &Pane) -> Vec<String> {
+            pane.extract(pane.visible_row_count())
+                .iter()
+                .map(|row| row.to_string())
+                .collect()
+        }
+
+        #[test]
+        fn test() {
+            let pane = Pane::new(
+                vec![StyledGraphemes::from("foo"), StyledGraphemes::from("bar")],
+                0,
+            );
+            let indicator = LoadingIndicator {
+                label: "searching",
+                show_elapsed: true,
+                ..LoadingIndicator::DEFAULT
+            };
+
+            let elapsed = Duration::from_millis(1500);
+            assert_eq!(
+                render(&indicator.overlay(Some(&pane), (10, 10), 1, elapsed, &Progress::default())),
+                vec!["foo", "bar ⠙ searching 1.5s"],
+            );
+            assert_eq!(
+                render(
+                    &LoadingIndicator {
+                        position: LoadingPosition::Prefix,
+                        ..indicator
+                    }
+                    .overlay(
+                        Some(&pane),
+                        (10, 10),
+                        1,
+                        elapsed,
+                        &Progress::default()
+                    )
+                ),
+                vec!["⠙ searching 1.5s foo", "bar"],
+            );
+            assert_eq!(
+                render(
+                    &LoadingIndicator {
+                        position: LoadingPosition::StatusLine,
+                        ..indicator
+                    }
+                    .overlay(
+                        Some(&pane),
+                        (10, 2),
+                        1,
+                        elapsed,
+                        &Progress::default()
+                    )
+                ),
+                vec!["foo", "⠙ searching 1.5s"],
+            );
+        }
+
+        #[test]
+        fn test_without_frames() {
+            let indicator = LoadingIndicator {
+                frames: &[],
+                label: "searching",
+                ..LoadingIndicator::DEFAULT
+            };
+
+            assert_eq!(
+                render(&indicator.overlay(None, (10, 10), 3, Duration::ZERO, &Progress::default())),
+                vec!["searching"],
+            );
+        }
+
+        #[test]
+        fn test_without_pane() {
+            assert_eq!(
+                render(&LoadingIndicator::DEFAULT.overlay(
+                    None,
+                    (10, 10),
+                    0,
+                    Duration::ZERO,
+                    &Progress::default()
+                )),
+                vec!["⠋"],
+            );
+        }
+
+        #[test]
+        fn test_with_progress() {
+            let progress = Progress {
+                completed: Some(3),
+                total: Some(10),
+                ..Default::default()
+            };
+
+            assert_eq!(
+                render(&LoadingIndicator::DEFAULT.overlay(
+                    None,
+                    (10, 10),
+                    0,
+                    Duration::ZERO,
+                    &progress
+                )),
+                vec!["⠋ 3/10"],
+            );
+        }
+    }
+}
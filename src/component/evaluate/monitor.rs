@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use tokio::sync::{watch, Notify};
+
+use super::{progress::Progress, Context, Status};
+
+/// Publishes what an evaluator is doing, for other components to observe.
+#[derive(Clone)]
+pub struct Monitor {
+    pub(super) progress: watch::Sender<Progress>,
+    pub(super) retry: Arc<Notify>,
+    pub(super) refresh: Arc<Notify>,
+    status: watch::Sender<Status>,
+}
+
+impl Default for Monitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Monitor {
+    pub fn new() -> Self {
+        Self {
+            progress: watch::Sender::new(Progress::default()),
+            retry: Arc::new(Notify::new()),
+            refresh: Arc::new(Notify::new()),
+            status: watch::Sender::new(Status::default()),
+        }
+    }
+
+    /// Subscribes to the progress reported by the current query or event group.
+    /// Event groups evaluated optimistically during a query do not report here.
+    pub fn progress(&self) -> watch::Receiver<Progress> {
+        self.progress.subscribe()
+    }
+
+    /// Clears the progress left by the previous evaluation
+    /// and returns the context for the next one.
+    pub(super) fn start_evaluation(&self, generation: u64) -> Context {
+        self.progress.send_replace(Progress::default());
+        Context::with_progress(self.progress.clone(), generation)
+    }
+
+    /// Subscribes to the evaluator's state, current query and queue length,
+    /// e.g. to show "searching…" in a status bar.
+    pub fn status(&self) -> watch::Receiver<Status> {
+        self.status.subscribe()
+    }
+
+    pub(super) fn publish(&self, status: Status) {
+        self.status.send_if_modified(|current| {
+            let modified = *current != status;
+            *current = status;
+            modified
+        });
+    }
+
+    /// Evaluates the query or event group that last exceeded its deadline again,
+    /// or the last query if none did.
+    pub fn retry(&self) {
+        self.retry.notify_one();
+    }
+
+    /// Evaluates the last query again once the current evaluation
+    /// and the queued event groups are done,
+    /// e.g. after the data it is evaluated against has grown.
+    /// Unlike `retry`, nothing in flight is cancelled.
+    pub fn refresh(&self) {
+        self.refresh.notify_one();
+    }
+}
@@ -0,0 +1,86 @@
+use std::{ops::Range, sync::Arc};
+
+use super::Context;
+
+impl Context {
+    /// Splits `0..len` into contiguous ranges, one per worker,
+    /// and runs `f` on each of them in parallel on the blocking thread pool.
+    /// `workers` of 0 means one worker per available CPU.
+    ///
+    /// Returns the partial results in range order, or `None` as soon as
+    /// this evaluation is cancelled. Workers should check `ctx.is_cancelled()`
+    /// regularly so that all shards stop together.
+    pub async fn shard<T, F>(&self, len: usize, workers: usize, f: F) -> Option<Vec<T>>
+    where
+        T: Send + 'static,
+        F: Fn(Range<usize>, &Context) -> T + Send + Sync + 'static,
+    {
+        let workers = match workers {
+            0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        }
+        .min(len.max(1));
+        let chunk = len.div_ceil(workers);
+        let f = Arc::new(f);
+
+        let handles = (0..workers).map(|i| {
+            let range = (i * chunk).min(len)..((i + 1) * chunk).min(len);
+            let f = f.clone();
+            let ctx = self.clone();
+            tokio::task::spawn_blocking(move || f(range, &ctx))
+        });
+
+        let results = tokio::select! {
+            results = futures::future::join_all(handles) => results,
+            _ = self.cancelled() => return None,
+        };
+        if self.is_cancelled() {
+            return None;
+        }
+
+        Some(
+            results
+                .into_iter()
+                .map(|result| result.unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic())))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod shard {
+        use super::*;
+
+        #[tokio::test]
+        async fn test() {
+            let ctx = Context::new();
+
+            let partials = ctx.shard(10, 4, |range, _| range.collect::<Vec<_>>()).await;
+
+            assert_eq!(
+                partials,
+                Some(vec![vec![0, 1, 2], vec![3, 4, 5], vec![6, 7, 8], vec![9]])
+            );
+        }
+
+        #[tokio::test]
+        async fn test_cancelled() {
+            let ctx = Context::new();
+            ctx.token.cancel();
+
+            let partials = ctx
+                .shard(10, 2, |range, ctx| {
+                    while !ctx.is_cancelled() {
+                        std::thread::yield_now();
+                    }
+                    range.len()
+                })
+                .await;
+
+            assert_eq!(partials, None);
+        }
+    }
+}
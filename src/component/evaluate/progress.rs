@@ -0,0 +1,117 @@
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+
+/// Progress of a long-running evaluation, as reported by the evaluator.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Progress {
+    /// Fraction complete, from 0.0 to 1.0.
+    pub fraction: Option<f64>,
+    /// Number of items processed so far.
+    pub completed: Option<usize>,
+    /// Total number of items, if known.
+    pub total: Option<usize>,
+    pub status: String,
+}
+
+impl Progress {
+    const BAR_WIDTH: usize = 10;
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Renders the progress as a bar (or counter) followed by the status text.
+    pub fn render(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(fraction) = self.fraction {
+            let fraction = fraction.clamp(0.0, 1.0);
+            let filled = (fraction * Self::BAR_WIDTH as f64).round() as usize;
+            parts.push(format!(
+                "[{}{}] {:>3.0}%",
+                "#".repeat(filled),
+                " ".repeat(Self::BAR_WIDTH - filled),
+                fraction * 100.0,
+            ));
+        }
+        match (self.completed, self.total) {
+            (Some(completed), Some(total)) => parts.push(format!("{}/{}", completed, total)),
+            (Some(completed), None) => parts.push(completed.to_string()),
+            _ => {}
+        }
+        if !self.status.is_empty() {
+            parts.push(self.status.clone());
+        }
+        parts.join(" ")
+    }
+}
+
+/// Handle for an evaluation to report its progress.
+/// Reports made after the evaluation has been cancelled are ignored.
+#[derive(Clone)]
+pub struct ProgressReporter {
+    sender: watch::Sender<Progress>,
+    token: CancellationToken,
+}
+
+impl ProgressReporter {
+    pub(crate) fn new(sender: watch::Sender<Progress>, token: CancellationToken) -> Self {
+        Self { sender, token }
+    }
+
+    pub fn report(&self, progress: Progress) {
+        self.update(|current| *current = progress);
+    }
+
+    pub fn set_fraction(&self, fraction: f64) {
+        self.update(|current| current.fraction = Some(fraction));
+    }
+
+    pub fn set_count(&self, completed: usize, total: Option<usize>) {
+        self.update(|current| {
+            current.completed = Some(completed);
+            current.total = total;
+        });
+    }
+
+    pub fn set_status<S: Into<String>>(&self, status: S) {
+        let status = status.into();
+        self.update(|current| current.status = status);
+    }
+
+    fn update<F: FnOnce(&mut Progress)>(&self, f: F) {
+        if !self.token.is_cancelled() {
+            self.sender.send_modify(f);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod render {
+        use super::*;
+
+        #[test]
+        fn test() {
+            let progress = Progress {
+                fraction: Some(0.5),
+                completed: Some(50),
+                total: Some(100),
+                status: String::from("filtering"),
+            };
+
+            assert_eq!(progress.render(), "[#####     ]  50% 50/100 filtering");
+        }
+
+        #[test]
+        fn test_counter_only() {
+            let progress = Progress {
+                completed: Some(3),
+                ..Default::default()
+            };
+
+            assert_eq!(progress.render(), "3");
+        }
+    }
+}
@@ -0,0 +1,320 @@
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use promkit::{
+    crossterm::style::{Attribute, Attributes, Color, ContentStyle},
+    grapheme::StyledGraphemes,
+    pane::Pane,
+    style::StyleBuilder,
+};
+use tokio::{
+    sync::mpsc,
+    task::JoinHandle,
+    time::{sleep, Sleep},
+};
+
+use crate::Event;
+
+use super::{Context, Evaluator, LoadingIndicator, LoadingPosition, Monitor};
+
+mod matcher;
+pub use matcher::Match;
+use matcher::{graphemes, Pattern};
+
+/// An fzf-style picker over candidates arriving from a stream.
+///
+/// Queries are matched in the background, in parallel,
+/// and each new query cancels the evaluation of the previous one.
+/// While candidates keep arriving, the current query is evaluated again
+/// every `REFRESH_INTERVAL`, once its previous evaluation and the queued
+/// events are done. The selection is moved by `VerticalCursorBuffer`.
+///
+/// Refreshing relies on the finder's own [`Monitor`], so use `run`,
+/// or pass `monitor()` to `run_monitored`.
+#[derive(Clone)]
+pub struct Finder {
+    candidates: Arc<RwLock<Vec<String>>>,
+    view: Arc<Mutex<View>>,
+    monitor: Monitor,
+    _ingestion: Arc<Ingestion>,
+}
+
+#[derive(Default)]
+struct View {
+    /// Generation of the evaluation that produced `matches`.
+    generation: u64,
+    query: String,
+    matches: Vec<Match>,
+    /// Number of candidates the query was matched against.
+    total: usize,
+    selected: usize,
+}
+
+/// Stops reading the candidates once the last finder is dropped.
+struct Ingestion(JoinHandle<()>);
+
+impl Drop for Ingestion {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+impl Finder {
+    pub const REFRESH_INTERVAL: Duration = Duration::from_millis(100);
+    /// Number of candidates each worker matches between cancellation checks.
+    const BATCH: usize = 1024;
+
+    /// Starts reading `candidates` in the background.
+    /// Must be called within a Tokio runtime.
+    pub fn new<S>(candidates: S) -> Self
+    where
+        S: Stream<Item = String> + Send + 'static,
+    {
+        let store = Arc::new(RwLock::new(Vec::new()));
+        let monitor = Monitor::new();
+        let handle = {
+            let store = store.clone();
+            let monitor = monitor.clone();
+            tokio::spawn(async move {
+                let mut chunks = Box::pin(candidates.ready_chunks(Self::BATCH));
+                let mut refresh: Option<Pin<Box<Sleep>>> = None;
+                loop {
+                    tokio::select! {
+                        chunk = chunks.next() => match chunk {
+                            Some(chunk) => {
+                                store.write().unwrap().extend(chunk);
+                                refresh.get_or_insert_with(|| Box::pin(sleep(Self::REFRESH_INTERVAL)));
+                            }
+                            None => {
+                                if refresh.is_some() {
+                                    monitor.refresh();
+                                }
+                                break;
+                            }
+                        },
+                        _ = async { refresh.as_mut().unwrap().await }, if refresh.is_some() => {
+                            refresh = None;
+                            monitor.refresh();
+                        }
+                    }
+                }
+            })
+        };
+
+        Self {
+            candidates: store,
+            view: Default::default(),
+            monitor,
+            _ingestion: Arc::new(Ingestion(handle)),
+        }
+    }
+
+    pub fn monitor(&self) -> Monitor {
+        self.monitor.clone()
+    }
+
+    /// Returns the selected candidate, if any matches the query.
+    pub fn selected(&self) -> Option<String> {
+        let view = self.view.lock().unwrap();
+        let index = view.matches.get(view.selected)?.index;
+        self.candidates.read().unwrap().get(index).cloned()
+    }
+
+    fn render(&self, view: &View, area: (u16, u16)) -> Pane {
+        let rows = (area.1 as usize).saturating_sub(1).max(1);
+        let first = (view.selected + 1).saturating_sub(rows);
+        let highlight = StyleBuilder::new()
+            .fgc(Color::Red)
+            .attrs(Attributes::from(Attribute::Bold))
+            .build();
+        let cursor = StyleBuilder::new().fgc(Color::Cyan).build();
+
+        let candidates = self.candidates.read().unwrap();
+        let mut layout = vec![StyledGraphemes::from(format!(
+            "{}/{}",
+            view.matches.len(),
+            view.total
+        ))];
+        layout.extend(
+            view.matches
+                .iter()
+                .enumerate()
+                .skip(first)
+                .take(rows)
+                .map(|(i, m)| {
+                    let text: StyledGraphemes = graphemes(&candidates[m.index])
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, grapheme)| {
+                            let style = if m.positions.binary_search(&i).is_ok() {
+                                highlight
+                            } else {
+                                ContentStyle::default()
+                            };
+                            StyledGraphemes::from_str(grapheme, style)
+                        })
+                        .collect();
+                    let marker = if i == view.selected {
+                        StyledGraphemes::from_str("❯ ", cursor)
+                    } else {
+                        StyledGraphemes::from("  ")
+                    };
+                    StyledGraphemes::from_iter([&marker, &text])
+                }),
+        );
+        Pane::new(layout, 0)
+    }
+}
+
+#[async_trait]
+impl Evaluator for Finder {
+    const LOADING_INDICATOR: LoadingIndicator = LoadingIndicator {
+        position: LoadingPosition::Prefix,
+        ..LoadingIndicator::DEFAULT
+    };
+
+    async fn process_query(&mut self, area: (u16, u16), query: String, ctx: Context) -> Pane {
+        let total = self.candidates.read().unwrap().len();
+        let pattern = Pattern::new(&query);
+        let candidates = self.candidates.clone();
+        let Some(parts) = ctx
+            .shard(total, 0, move |range, ctx| {
+                let mut matches = Vec::new();
+                for batch in range.clone().step_by(Self::BATCH) {
+                    if ctx.is_cancelled() {
+                        break;
+                    }
+                    let end = (batch + Self::BATCH).min(range.end);
+                    // Locked per batch so that ingestion is not held up for the whole range.
+                    let candidates = candidates.read().unwrap();
+                    matches.extend((batch..end).filter_map(|i| pattern.matches(i, &candidates[i])));
+                }
+                matches.sort_by_key(|m| std::cmp::Reverse(m.score));
+                matches
+            })
+            .await
+        else {
+            return Pane::new(vec![], 0);
+        };
+        let mut matches: Vec<Match> = parts.into_iter().flatten().collect();
+        // The parts are already sorted, so this only merges them.
+        matches.sort_by_key(|m| std::cmp::Reverse(m.score));
+
+        let mut view = self.view.lock().unwrap();
+        if ctx.generation() < view.generation {
+            return Pane::new(vec![], 0);
+        }
+        if view.query != query {
+            view.selected = 0;
+        }
+        view.selected = view.selected.min(matches.len().saturating_sub(1));
+        view.generation = ctx.generation();
+        view.query = query;
+        view.matches = matches;
+        view.total = total;
+        self.render(&view, area)
+    }
+
+    async fn process_events(&mut self, area: (u16, u16), events: Vec<Event>, _: Context) -> Pane {
+        let mut view = self.view.lock().unwrap();
+        for event in &events {
+            if let Event::VerticalCursorBuffer(up, down) = event {
+                view.selected = (view.selected + down)
+                    .saturating_sub(*up)
+                    .min(view.matches.len().saturating_sub(1));
+            }
+        }
+        self.render(&view, area)
+    }
+
+    /// Runs with the finder's own monitor, so that arriving candidates
+    /// refresh the matches. Nothing is listed until the first query,
+    /// which `TextEditor::queries` sends right away.
+    async fn run(
+        &mut self,
+        area: (u16, u16),
+        query_rx: mpsc::Receiver<String>,
+        events_rx: mpsc::Receiver<Vec<Event>>,
+        tx: mpsc::Sender<Pane>,
+    ) {
+        let monitor = self.monitor();
+        self.run_monitored(area, query_rx, events_rx, tx, monitor)
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod run {
+        use super::*;
+
+        /// Waits for a pane whose rows satisfy `f`.
+        async fn until(rx: &mut mpsc::Receiver<Pane>, f: impl Fn(&[String]) -> bool) {
+            tokio::time::timeout(Duration::from_secs(5), async {
+                loop {
+                    let pane = rx.recv().await.unwrap();
+                    let rows: Vec<String> = pane
+                        .extract(pane.visible_row_count())
+                        .iter()
+                        .map(|row| row.to_string())
+                        .collect();
+                    if f(&rows) {
+                        break;
+                    }
+                }
+            })
+            .await
+            .unwrap();
+        }
+
+        #[tokio::test(flavor = "multi_thread")]
+        async fn test() {
+            let (candidates_tx, candidates_rx) = mpsc::channel(8);
+            let (query_tx, query_rx) = mpsc::channel(1);
+            let (events_tx, events_rx) = mpsc::channel(1);
+            let (tx, mut rx) = mpsc::channel(1);
+
+            let finder = Finder::new(tokio_stream::wrappers::ReceiverStream::new(candidates_rx));
+            let handle = {
+                let mut finder = finder.clone();
+                tokio::spawn(async move { finder.run((80, 10), query_rx, events_rx, tx).await })
+            };
+
+            query_tx.send(String::new()).await.unwrap();
+            for candidate in ["src/lib.rs", "README.md", "src/event.rs"] {
+                candidates_tx.send(candidate.to_string()).await.unwrap();
+            }
+            until(&mut rx, |rows| {
+                rows.first().is_some_and(|row| row.ends_with("3/3"))
+            })
+            .await;
+
+            query_tx.send(String::from("srs")).await.unwrap();
+            until(&mut rx, |rows| {
+                rows == ["2/3", "❯ src/lib.rs", "  src/event.rs"]
+            })
+            .await;
+
+            // Candidates arriving later are matched against the current query.
+            candidates_tx.send(String::from("src/rs")).await.unwrap();
+            until(&mut rx, |rows| rows.len() == 4 && rows[1] == "❯ src/rs").await;
+
+            events_tx
+                .send(vec![Event::VerticalCursorBuffer(0, 2)])
+                .await
+                .unwrap();
+            until(&mut rx, |rows| rows[3].starts_with("❯ ")).await;
+            assert_eq!(finder.selected().as_deref(), Some("src/event.rs"));
+
+            drop((query_tx, events_tx));
+            handle.await.unwrap();
+        }
+    }
+}
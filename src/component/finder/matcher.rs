@@ -0,0 +1,174 @@
+use promkit::grapheme::StyledGrapheme;
+
+/// A candidate matching the query of a [`Finder`](super::Finder).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Match {
+    /// Index of the candidate in arrival order.
+    pub index: usize,
+    pub score: i64,
+    /// Indices of the matched graphemes in the candidate, as split by [`graphemes`].
+    pub positions: Vec<usize>,
+}
+
+/// A query ready to be matched against many candidates.
+/// Matching is case-insensitive unless the query contains an uppercase letter.
+#[derive(Clone, Debug)]
+pub(crate) struct Pattern {
+    chars: Vec<char>,
+    case_sensitive: bool,
+}
+
+impl Pattern {
+    pub fn new(query: &str) -> Self {
+        Self {
+            chars: graphemes(query)
+                .iter()
+                .filter_map(|g| g.chars().next())
+                .collect(),
+            case_sensitive: query.chars().any(char::is_uppercase),
+        }
+    }
+
+    fn eq(&self, a: char, b: char) -> bool {
+        if self.case_sensitive {
+            a == b
+        } else {
+            a.to_lowercase().eq(b.to_lowercase())
+        }
+    }
+
+    /// Finds the query as a subsequence of `candidate`,
+    /// preferring the shortest occurrence, and scores it.
+    pub fn matches(&self, index: usize, candidate: &str) -> Option<Match> {
+        if self.chars.is_empty() {
+            return Some(Match {
+                index,
+                score: 0,
+                positions: vec![],
+            });
+        }
+        // Graphemes are matched by the character they start with.
+        let chars: Vec<char> = graphemes(candidate)
+            .iter()
+            .filter_map(|g| g.chars().next())
+            .collect();
+
+        // Earliest end of an occurrence.
+        let mut matched = 0;
+        let end = chars.iter().position(|&c| {
+            if self.eq(c, self.chars[matched]) {
+                matched += 1;
+            }
+            matched == self.chars.len()
+        })?;
+
+        // Latest start of an occurrence ending there.
+        let mut remaining = self.chars.len();
+        let start = (0..=end).rev().find(|&i| {
+            if self.eq(chars[i], self.chars[remaining - 1]) {
+                remaining -= 1;
+            }
+            remaining == 0
+        })?;
+
+        let mut positions = Vec::with_capacity(self.chars.len());
+        for (i, &c) in chars.iter().enumerate().take(end + 1).skip(start) {
+            if positions.len() < self.chars.len() && self.eq(c, self.chars[positions.len()]) {
+                positions.push(i);
+            }
+        }
+
+        let score = positions
+            .iter()
+            .enumerate()
+            .map(|(n, &pos)| {
+                let mut score = 16;
+                if is_word_start(&chars, pos) {
+                    score += 8;
+                }
+                if n > 0 {
+                    let gap = pos - positions[n - 1] - 1;
+                    score += if gap == 0 { 8 } else { -(gap as i64) };
+                }
+                score
+            })
+            .sum();
+
+        Some(Match {
+            index,
+            score,
+            positions,
+        })
+    }
+}
+
+/// Splits `text` into graphemes: each character together with
+/// the zero-width characters following it, such as combining marks.
+pub(crate) fn graphemes(text: &str) -> Vec<&str> {
+    let mut graphemes = Vec::new();
+    let mut start = 0;
+    for (i, ch) in text.char_indices().skip(1) {
+        if StyledGrapheme::from(ch).width() > 0 {
+            graphemes.push(&text[start..i]);
+            start = i;
+        }
+    }
+    if !text.is_empty() {
+        graphemes.push(&text[start..]);
+    }
+    graphemes
+}
+
+fn is_word_start(chars: &[char], pos: usize) -> bool {
+    match pos.checked_sub(1).map(|prev| chars[prev]) {
+        None => true,
+        Some(prev) => {
+            matches!(prev, ' ' | '/' | '\\' | '_' | '-' | '.' | ':')
+                || (prev.is_lowercase() && chars[pos].is_uppercase())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod matches {
+        use super::*;
+
+        #[test]
+        fn test() {
+            let pattern = Pattern::new("abc");
+            assert_eq!(
+                pattern.matches(0, "xaxabxc").map(|m| m.positions),
+                Some(vec![3, 4, 6])
+            );
+            assert_eq!(pattern.matches(0, "ACB"), None);
+            assert_eq!(Pattern::new("Ab").matches(0, "ab"), None);
+
+            let score = |candidate| pattern.matches(0, candidate).unwrap().score;
+            assert!(score("abc") > score("a_b_c"));
+            assert!(score("x_abc") > score("xabc"));
+        }
+
+        #[test]
+        fn test_combining_marks() {
+            assert_eq!(
+                Pattern::new("ex")
+                    .matches(0, "e\u{301}x")
+                    .map(|m| m.positions),
+                Some(vec![0, 1])
+            );
+        }
+    }
+
+    mod graphemes {
+        use super::*;
+
+        #[test]
+        fn test() {
+            assert_eq!(graphemes("e\u{301}x"), vec!["e\u{301}", "x"]);
+            assert!(graphemes("").is_empty());
+        }
+    }
+}
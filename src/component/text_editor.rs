@@ -0,0 +1,163 @@
+use promkit::{pane::Pane, switch::ActiveKeySwitcher, text_editor, PaneFactory};
+use tokio::sync::{mpsc, watch};
+
+use crate::Event;
+
+use super::InputProcessor;
+
+pub mod keymap;
+
+pub type Handler = fn(&[Event], &mut EditorState) -> anyhow::Result<()>;
+
+/// What a keymap edits.
+#[derive(Clone)]
+pub struct EditorState {
+    pub editor: text_editor::State,
+    /// The text most recently killed, inserted back by yanking.
+    pub kill_buffer: String,
+}
+
+impl EditorState {
+    /// Returns the text without the trailing cursor.
+    pub fn text(&self) -> String {
+        self.editor.texteditor.text_without_cursor().to_string()
+    }
+}
+
+/// A single-line editor wrapping promkit's `text_editor::State`.
+///
+/// Its text is published on every change, so it can drive
+/// the queries of an [`Evaluator`](super::Evaluator).
+pub struct TextEditor {
+    keymap: ActiveKeySwitcher<Handler>,
+    state: EditorState,
+    text: watch::Sender<String>,
+}
+
+impl TextEditor {
+    pub fn new(editor: text_editor::State) -> Self {
+        let state = EditorState {
+            editor,
+            kill_buffer: String::new(),
+        };
+        Self {
+            keymap: ActiveKeySwitcher::new("default", self::keymap::default as Handler),
+            text: watch::Sender::new(state.text()),
+            state,
+        }
+    }
+
+    pub fn keymap(mut self, keymap: ActiveKeySwitcher<Handler>) -> Self {
+        self.keymap = keymap;
+        self
+    }
+
+    pub fn state(&self) -> &EditorState {
+        &self.state
+    }
+
+    /// Replaces the state, e.g. to restore an earlier one,
+    /// publishing its text if it changed.
+    pub fn set_state(&mut self, state: EditorState) {
+        self.state = state;
+        self.publish();
+    }
+
+    fn publish(&self) {
+        let text = self.state.text();
+        self.text.send_if_modified(|current| {
+            if *current == text {
+                return false;
+            }
+            *current = text;
+            true
+        });
+    }
+
+    /// Returns a receiver always holding the latest text.
+    pub fn subscribe(&self) -> watch::Receiver<String> {
+        self.text.subscribe()
+    }
+
+    /// Returns a channel receiving the current text and then the text
+    /// whenever it changes, to be passed to `Evaluator::run` as its queries.
+    /// Changes made while the receiver is busy are coalesced.
+    /// Must be called within a Tokio runtime.
+    pub fn queries(&self) -> mpsc::Receiver<String> {
+        let mut text = self.text.subscribe();
+        text.mark_changed();
+        let (tx, rx) = mpsc::channel(1);
+        tokio::spawn(async move {
+            while text.changed().await.is_ok() {
+                let latest = text.borrow_and_update().clone();
+                if tx.send(latest).await.is_err() {
+                    break;
+                }
+            }
+        });
+        rx
+    }
+}
+
+impl InputProcessor<Vec<Event>> for TextEditor {
+    /// A failing keymap leaves the state unchanged.
+    fn process_event(&mut self, area: (u16, u16), inputs: Vec<Event>) -> Pane {
+        let mut state = self.state.clone();
+        if self.keymap.get()(&inputs, &mut state).is_ok() {
+            self.state = state;
+            self.publish();
+        }
+        self.state.editor.create_pane(area.0, area.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(super) fn state(text: &str) -> text_editor::State {
+        text_editor::State {
+            texteditor: text_editor::TextEditor::new(text),
+            history: Some(Default::default()),
+            prefix: String::new(),
+            mask: None,
+            prefix_style: Default::default(),
+            active_char_style: Default::default(),
+            inactive_char_style: Default::default(),
+            edit_mode: Default::default(),
+            word_break_chars: [' '].into_iter().collect(),
+            lines: None,
+        }
+    }
+
+    mod queries {
+        use super::*;
+
+        use std::time::Duration;
+
+        #[tokio::test]
+        async fn test() {
+            let mut editor = TextEditor::new(state(""));
+            let mut queries = editor.queries();
+
+            editor.process_event((80, 1), vec![Event::KeyBuffer(vec!['a', 'b'])]);
+            assert_eq!(queries.recv().await, Some(String::from("ab")));
+
+            // Moving the cursor does not change the text.
+            editor.process_event((80, 1), vec![Event::HorizontalCursorBuffer(1, 0)]);
+            editor.process_event((80, 1), vec![Event::KeyBuffer(vec!['c'])]);
+            assert_eq!(queries.recv().await, Some(String::from("acb")));
+        }
+
+        #[tokio::test]
+        async fn test_sends_initial_text() {
+            let mut editor = TextEditor::new(state("ab"));
+            let mut queries = editor.queries();
+
+            let initial = tokio::time::timeout(Duration::from_secs(1), queries.recv()).await;
+            assert_eq!(initial, Ok(Some(String::from("ab"))));
+            editor.process_event((80, 1), vec![Event::KeyBuffer(vec!['c'])]);
+            assert_eq!(queries.recv().await, Some(String::from("abc")));
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use promkit::{
+    crossterm::{
+        self,
+        event::{KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers},
+    },
+    text_editor,
+};
+
+use crate::Event;
+
+use super::EditorState;
+
+/// Emacs-like bindings:
+///
+/// | Key | Action |
+/// | --- | --- |
+/// | ←, →, Ctrl+B, Ctrl+F | Move the cursor |
+/// | Ctrl+A, Home / Ctrl+E, End | Move to the head / tail |
+/// | Alt+B / Alt+F | Move to the previous / next word |
+/// | ↑ / ↓ | Recall the previous / next history entry |
+/// | Enter | Add the text to the history |
+/// | Backspace / Delete, Ctrl+D | Erase the previous / next char |
+/// | Ctrl+W / Alt+D | Kill the previous / next word |
+/// | Ctrl+U / Ctrl+K | Kill to the head / tail |
+/// | Ctrl+Y | Yank the last killed text |
+/// | Insert | Toggle insert / overwrite mode |
+pub fn default(event_buffer: &[Event], state: &mut EditorState) -> anyhow::Result<()> {
+    for event in event_buffer {
+        match event {
+            Event::KeyBuffer(chars) => insert(state, chars),
+            Event::HorizontalCursorBuffer(left, right) => {
+                state.editor.texteditor.shift(*left, *right);
+            }
+            Event::VerticalCursorBuffer(up, down) => recall(state, *up, *down),
+            Event::Others(e, times) => {
+                let Some(key) = key(e) else {
+                    continue;
+                };
+                for _ in 0..*times {
+                    apply(state, key);
+                }
+            }
+            Event::LastResize(..) => {}
+        }
+    }
+    Ok(())
+}
+
+fn key(event: &crossterm::event::Event) -> Option<(KeyCode, KeyModifiers)> {
+    match event {
+        crossterm::event::Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => Some((*code, *modifiers)),
+        _ => None,
+    }
+}
+
+fn apply(state: &mut EditorState, key: (KeyCode, KeyModifiers)) {
+    let word_break_chars = state.editor.word_break_chars.clone();
+    let texteditor = &mut state.editor.texteditor;
+    match key {
+        (KeyCode::Char('b'), KeyModifiers::CONTROL) => {
+            texteditor.backward();
+        }
+        (KeyCode::Char('f'), KeyModifiers::CONTROL) => {
+            texteditor.forward();
+        }
+        (KeyCode::Char('a'), KeyModifiers::CONTROL) | (KeyCode::Home, KeyModifiers::NONE) => {
+            texteditor.move_to_head()
+        }
+        (KeyCode::Char('e'), KeyModifiers::CONTROL) | (KeyCode::End, KeyModifiers::NONE) => {
+            texteditor.move_to_tail()
+        }
+        (KeyCode::Char('b'), KeyModifiers::ALT) => {
+            texteditor.move_to_previous_nearest(&word_break_chars)
+        }
+        (KeyCode::Char('f'), KeyModifiers::ALT) => {
+            texteditor.move_to_next_nearest(&word_break_chars)
+        }
+
+        (KeyCode::Enter, KeyModifiers::NONE) => {
+            let text = state.text();
+            if let Some(history) = &mut state.editor.history {
+                if !text.is_empty() {
+                    history.insert(text);
+                }
+            }
+        }
+
+        (KeyCode::Backspace, KeyModifiers::NONE) => texteditor.erase(),
+        (KeyCode::Delete, KeyModifiers::NONE) | (KeyCode::Char('d'), KeyModifiers::CONTROL) => {
+            erase_next(texteditor)
+        }
+
+        (KeyCode::Char('w'), KeyModifiers::CONTROL) => kill(state, |texteditor| {
+            texteditor.erase_to_previous_nearest(&word_break_chars)
+        }),
+        (KeyCode::Char('d'), KeyModifiers::ALT) => kill(state, |texteditor| {
+            texteditor.erase_to_next_nearest(&word_break_chars)
+        }),
+        (KeyCode::Char('u'), KeyModifiers::CONTROL) => kill(state, |texteditor| {
+            for _ in 0..texteditor.position() {
+                texteditor.erase();
+            }
+        }),
+        (KeyCode::Char('k'), KeyModifiers::CONTROL) => kill(state, |texteditor| {
+            let position = texteditor.position();
+            let len = texteditor.text_without_cursor().len();
+            texteditor.move_to_tail();
+            for _ in position..len {
+                texteditor.erase();
+            }
+        }),
+        (KeyCode::Char('y'), KeyModifiers::CONTROL) => {
+            let chars = state.kill_buffer.chars().collect();
+            insert(state, &chars);
+        }
+
+        (KeyCode::Insert, KeyModifiers::NONE) => {
+            state.editor.edit_mode = match state.editor.edit_mode {
+                text_editor::Mode::Insert => text_editor::Mode::Overwrite,
+                text_editor::Mode::Overwrite => text_editor::Mode::Insert,
+            }
+        }
+        _ => {}
+    }
+}
+
+fn insert(state: &mut EditorState, chars: &Vec<char>) {
+    match state.editor.edit_mode {
+        text_editor::Mode::Insert => state.editor.texteditor.insert_chars(chars),
+        text_editor::Mode::Overwrite => state.editor.texteditor.overwrite_chars(chars),
+    }
+}
+
+fn erase_next(texteditor: &mut text_editor::TextEditor) {
+    if texteditor.forward() {
+        texteditor.erase();
+    }
+}
+
+fn recall(state: &mut EditorState, up: usize, down: usize) {
+    let Some(history) = &mut state.editor.history else {
+        return;
+    };
+    let mut moved = false;
+    for _ in 0..up {
+        moved |= history.backward();
+    }
+    for _ in 0..down {
+        moved |= history.forward();
+    }
+    if moved {
+        state.editor.texteditor.replace(&history.get());
+    }
+}
+
+/// Applies `erase` and keeps the erased text in the kill buffer.
+fn kill<F: FnOnce(&mut text_editor::TextEditor)>(state: &mut EditorState, erase: F) {
+    let before: Vec<char> = state.text().chars().collect();
+    erase(&mut state.editor.texteditor);
+    let start = state.editor.texteditor.position();
+    let erased = before.len() - state.text().chars().count();
+    if erased > 0 {
+        state.kill_buffer = before[start..start + erased].iter().collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::text_editor::tests::state;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> Event {
+        Event::Others(
+            crossterm::event::Event::Key(KeyEvent::new(code, modifiers)),
+            1,
+        )
+    }
+
+    fn edit(text: &str, events: Vec<Event>) -> EditorState {
+        let mut state = EditorState {
+            editor: state(text),
+            kill_buffer: String::new(),
+        };
+        default(&events, &mut state).unwrap();
+        state
+    }
+
+    mod default {
+        use super::*;
+
+        #[test]
+        fn test_kill_yank() {
+            let state = edit(
+                "foo bar baz",
+                vec![
+                    key(KeyCode::Char('w'), KeyModifiers::CONTROL),
+                    key(KeyCode::Char('a'), KeyModifiers::CONTROL),
+                    key(KeyCode::Char('y'), KeyModifiers::CONTROL),
+                ],
+            );
+            assert_eq!(state.text(), "bazfoo bar ");
+            assert_eq!(state.kill_buffer, "baz");
+
+            let state = edit(
+                "foo bar",
+                vec![
+                    Event::HorizontalCursorBuffer(4, 0),
+                    key(KeyCode::Char('k'), KeyModifiers::CONTROL),
+                    key(KeyCode::Char('a'), KeyModifiers::CONTROL),
+                    key(KeyCode::Char('y'), KeyModifiers::CONTROL),
+                ],
+            );
+            assert_eq!(state.text(), " barfoo");
+
+            let state = edit(
+                "foo bar",
+                vec![
+                    Event::HorizontalCursorBuffer(3, 0),
+                    key(KeyCode::Char('u'), KeyModifiers::CONTROL),
+                ],
+            );
+            assert_eq!(
+                (state.text().as_str(), state.kill_buffer.as_str()),
+                ("bar", "foo ")
+            );
+        }
+
+        #[test]
+        fn test_word_moves() {
+            let state = edit(
+                "foo bar baz",
+                vec![
+                    key(KeyCode::Char('b'), KeyModifiers::ALT),
+                    key(KeyCode::Char('b'), KeyModifiers::ALT),
+                    Event::KeyBuffer(vec!['_']),
+                    key(KeyCode::Char('f'), KeyModifiers::ALT),
+                    Event::KeyBuffer(vec!['!']),
+                ],
+            );
+            assert_eq!(state.text(), "foo _bar !baz");
+        }
+
+        #[test]
+        fn test_history() {
+            let state = edit(
+                "",
+                vec![
+                    Event::KeyBuffer(vec!['a']),
+                    key(KeyCode::Enter, KeyModifiers::NONE),
+                    key(KeyCode::Char('u'), KeyModifiers::CONTROL),
+                    Event::KeyBuffer(vec!['b']),
+                    key(KeyCode::Enter, KeyModifiers::NONE),
+                    Event::VerticalCursorBuffer(2, 0),
+                ],
+            );
+            assert_eq!(state.text(), "a");
+
+            let state = edit(
+                "",
+                vec![
+                    Event::KeyBuffer(vec!['a']),
+                    key(KeyCode::Enter, KeyModifiers::NONE),
+                    Event::VerticalCursorBuffer(1, 1),
+                ],
+            );
+            assert_eq!(state.text(), "");
+        }
+    }
+}
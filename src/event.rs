@@ -1,5 +1,8 @@
 use promkit::crossterm;
 
+pub mod filter;
+pub use filter::EventFilter;
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum Event {
     KeyBuffer(Vec<char>),
@@ -8,3 +11,26 @@ pub enum Event {
     LastResize(u16, u16),                 // (width, height)
     Others(crossterm::event::Event, usize),
 }
+
+/// Discriminant of [`Event`] without its payload,
+/// used to subscribe to particular kinds of events.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    KeyBuffer,
+    VerticalCursorBuffer,
+    HorizontalCursorBuffer,
+    LastResize,
+    Others,
+}
+
+impl Event {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Event::KeyBuffer(_) => EventKind::KeyBuffer,
+            Event::VerticalCursorBuffer(..) => EventKind::VerticalCursorBuffer,
+            Event::HorizontalCursorBuffer(..) => EventKind::HorizontalCursorBuffer,
+            Event::LastResize(..) => EventKind::LastResize,
+            Event::Others(..) => EventKind::Others,
+        }
+    }
+}
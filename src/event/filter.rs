@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use super::{Event, EventKind};
+
+/// Decides which events of an event group are delivered to a component.
+#[derive(Clone)]
+pub struct EventFilter {
+    predicate: Arc<dyn Fn(&Event) -> bool + Send + Sync>,
+}
+
+impl Default for EventFilter {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl EventFilter {
+    /// Accepts every event.
+    pub fn all() -> Self {
+        Self::predicate(|_| true)
+    }
+
+    /// Accepts only events whose kind is in `kinds`.
+    pub fn kinds<I: IntoIterator<Item = EventKind>>(kinds: I) -> Self {
+        let kinds: Vec<EventKind> = kinds.into_iter().collect();
+        Self::predicate(move |event| kinds.contains(&event.kind()))
+    }
+
+    /// Accepts events for which `f` returns true.
+    pub fn predicate<F>(f: F) -> Self
+    where
+        F: Fn(&Event) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            predicate: Arc::new(f),
+        }
+    }
+
+    pub fn matches(&self, event: &Event) -> bool {
+        (self.predicate)(event)
+    }
+
+    /// Returns the subset of `events` accepted by this filter,
+    /// preserving their order.
+    pub fn apply(&self, events: &[Event]) -> Vec<Event> {
+        events
+            .iter()
+            .filter(|event| self.matches(event))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod apply {
+        use super::*;
+
+        #[test]
+        fn test_kinds() {
+            let events = vec![
+                Event::KeyBuffer(vec!['a']),
+                Event::HorizontalCursorBuffer(1, 0),
+                Event::VerticalCursorBuffer(0, 2),
+                Event::HorizontalCursorBuffer(0, 3),
+            ];
+
+            let filter = EventFilter::kinds([EventKind::HorizontalCursorBuffer]);
+
+            assert_eq!(
+                filter.apply(&events),
+                vec![
+                    Event::HorizontalCursorBuffer(1, 0),
+                    Event::HorizontalCursorBuffer(0, 3),
+                ]
+            );
+        }
+
+        #[test]
+        fn test_predicate() {
+            let events = vec![Event::KeyBuffer(vec!['a']), Event::KeyBuffer(vec!['b'])];
+
+            let filter = EventFilter::predicate(
+                |event| matches!(event, Event::KeyBuffer(chars) if chars.contains(&'b')),
+            );
+
+            assert_eq!(filter.apply(&events), vec![Event::KeyBuffer(vec!['b'])]);
+        }
+    }
+}
@@ -18,6 +18,8 @@ pub use event::Event;
 pub mod operator;
 use operator::TimeBasedOperator;
 pub mod snapshot;
+pub mod subscriber;
+pub use subscriber::{BackpressurePolicy, Subscriber};
 
 pub struct Prompt {}
 
@@ -31,7 +33,7 @@ impl Drop for Prompt {
 impl Prompt {
     pub async fn run(
         &mut self,
-        senders: Vec<mpsc::Sender<Vec<Event>>>,
+        subscribers: Vec<Subscriber>,
         receivers: Vec<mpsc::Receiver<Pane>>,
         delay: Duration,
     ) -> anyhow::Result<()> {
@@ -52,6 +54,8 @@ impl Prompt {
             .map(|_| Pane::new(vec![StyledGraphemes::from("")], 0))
             .collect();
 
+        let dispatchers: Vec<_> = subscribers.into_iter().map(Subscriber::spawn).collect();
+
         let pane_stream = futures::stream::select_all(
             receivers
                 .into_iter()
@@ -91,8 +95,8 @@ impl Prompt {
                     }
                 },
                 Some(event_groups) = event_group_receiver.recv() => {
-                    for sender in &senders {
-                        if let Err(e) = sender.send(event_groups.clone()).await {
+                    for dispatcher in &dispatchers {
+                        if let Err(e) = dispatcher.dispatch(&event_groups) {
                             result = Err(anyhow::anyhow!("Failed to send event groups: {}", e));
                             break 'main;
                         }
@@ -71,8 +71,8 @@ impl TimeBasedOperator {
                     last_resize = Some((*width, *height));
                     resize_index = Some(result.len());
                 }
-                event if Self::extract_char(&event).is_some() => {
-                    let ch = Self::extract_char(&event).unwrap();
+                event if Self::extract_char(event).is_some() => {
+                    let ch = Self::extract_char(event).unwrap();
                     Self::flush_non_char_buffers(
                         &mut result,
                         &mut current_vertical,
@@ -81,16 +81,16 @@ impl TimeBasedOperator {
                     );
                     current_chars.push(ch);
                 }
-                event if Self::detect_vertical_direction(&event).is_some() => {
-                    let (up, down) = Self::detect_vertical_direction(&event).unwrap();
+                event if Self::detect_vertical_direction(event).is_some() => {
+                    let (up, down) = Self::detect_vertical_direction(event).unwrap();
                     Self::flush_char_buffer(&mut result, &mut current_chars);
                     Self::flush_horizontal_buffer(&mut result, &mut current_horizontal);
                     Self::flush_others_buffer(&mut result, &mut current_others);
                     current_vertical.0 += up;
                     current_vertical.1 += down;
                 }
-                event if Self::detect_horizontal_direction(&event).is_some() => {
-                    let (left, right) = Self::detect_horizontal_direction(&event).unwrap();
+                event if Self::detect_horizontal_direction(event).is_some() => {
+                    let (left, right) = Self::detect_horizontal_direction(event).unwrap();
                     Self::flush_char_buffer(&mut result, &mut current_chars);
                     Self::flush_vertical_buffer(&mut result, &mut current_vertical);
                     Self::flush_others_buffer(&mut result, &mut current_others);
@@ -132,6 +132,52 @@ impl TimeBasedOperator {
         result
     }
 
+    /// Merges several event groups into one,
+    /// following the same aggregation rules as `process_events`:
+    /// adjacent key buffers are concatenated, adjacent cursor buffers are summed,
+    /// repeated identical events are counted, and only the last resize is kept.
+    pub fn merge_event_groups<I: IntoIterator<Item = Vec<Event>>>(groups: I) -> Vec<Event> {
+        let mut result: Vec<Event> = Vec::new();
+        let mut resize_index: Option<usize> = None;
+
+        for event in groups.into_iter().flatten() {
+            match (result.last_mut(), event) {
+                (_, Event::LastResize(width, height)) => {
+                    if let Some(idx) = resize_index.take() {
+                        result.remove(idx);
+                    }
+                    resize_index = Some(result.len());
+                    result.push(Event::LastResize(width, height));
+                }
+                (Some(Event::KeyBuffer(last)), Event::KeyBuffer(chars)) => {
+                    last.extend(chars);
+                }
+                (
+                    Some(Event::VerticalCursorBuffer(up, down)),
+                    Event::VerticalCursorBuffer(more_up, more_down),
+                ) => {
+                    *up += more_up;
+                    *down += more_down;
+                }
+                (
+                    Some(Event::HorizontalCursorBuffer(left, right)),
+                    Event::HorizontalCursorBuffer(more_left, more_right),
+                ) => {
+                    *left += more_left;
+                    *right += more_right;
+                }
+                (Some(Event::Others(last, count)), Event::Others(event, times))
+                    if *last == event =>
+                {
+                    *count += times;
+                }
+                (_, event) => result.push(event),
+            }
+        }
+
+        result
+    }
+
     fn flush_all_buffers(
         result: &mut Vec<Event>,
         chars: &mut Vec<char>,
@@ -388,4 +434,44 @@ mod tests {
             assert_eq!(TimeBasedOperator::process_events(&events), expected);
         }
     }
+    mod merge_event_groups {
+        use super::*;
+
+        #[test]
+        fn test() {
+            let ctrl_f = crossterm::event::Event::Key(KeyEvent {
+                code: KeyCode::Char('f'),
+                modifiers: KeyModifiers::CONTROL,
+                kind: KeyEventKind::Press,
+                state: KeyEventState::NONE,
+            });
+            let groups = vec![
+                vec![
+                    Event::KeyBuffer(vec!['a']),
+                    Event::LastResize(128, 128),
+                    Event::HorizontalCursorBuffer(1, 0),
+                ],
+                vec![
+                    Event::HorizontalCursorBuffer(2, 1),
+                    Event::Others(ctrl_f.clone(), 1),
+                ],
+                vec![
+                    Event::Others(ctrl_f.clone(), 2),
+                    Event::LastResize(64, 64),
+                    Event::KeyBuffer(vec!['b']),
+                ],
+                vec![Event::KeyBuffer(vec!['c'])],
+            ];
+
+            let expected = vec![
+                Event::KeyBuffer(vec!['a']),
+                Event::HorizontalCursorBuffer(3, 1),
+                Event::Others(ctrl_f, 3),
+                Event::LastResize(64, 64),
+                Event::KeyBuffer(vec!['b', 'c']),
+            ];
+
+            assert_eq!(TimeBasedOperator::merge_event_groups(groups), expected);
+        }
+    }
 }
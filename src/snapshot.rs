@@ -1,56 +1,294 @@
-use std::{future::Future, sync::Arc};
-use tokio::sync::Mutex;
+use std::{collections::VecDeque, future::Future, sync::Arc};
 
+use futures::stream::{self, BoxStream, StreamExt};
+use tokio::sync::{broadcast, watch, Mutex};
+
+mod derived;
+pub use derived::Derived;
+mod group;
+use group::Groups;
+pub use group::UpdateGroup;
+#[cfg(feature = "persist")]
+mod persist;
+#[cfg(feature = "persist")]
+pub use persist::Persistence;
+mod recorder;
+pub use recorder::{triggered_by, Record, Recorder};
+mod transaction;
+pub use transaction::{Conflict, Transaction};
+
+/// Shared state with history, versioning and change notifications.
+///
+/// States are kept behind `Arc`s, so history entries, subscribers
+/// and readers share them instead of cloning; a state is cloned
+/// only when it is about to be modified.
 pub struct AsyncSnapshot<T> {
     inner: Arc<Mutex<AsyncSnapshotInner<T>>>,
+    latest: watch::Sender<Arc<T>>,
+    changes: broadcast::Sender<Change<T>>,
+    groups: Arc<std::sync::Mutex<Groups>>,
 }
 
 struct AsyncSnapshotInner<T> {
-    current: T,
-    previous: Option<T>,
+    current: Arc<T>,
+    /// States that can be restored by `undo`, oldest first.
+    past: VecDeque<Arc<T>>,
+    /// States that can be restored by `redo`, most recently undone last.
+    future: Vec<Arc<T>>,
+    /// Maximum number of entries in `past`.
+    depth: usize,
+    /// The update group that has already recorded its undo step.
+    grouped: Option<u64>,
+    /// Incremented on every change of `current`.
+    version: u64,
+    recorder: Option<Recorder<T>>,
+}
+
+impl<T> AsyncSnapshotInner<T> {
+    /// Replaces `current` within the open update `group`, if any.
+    fn replace(&mut self, new_state: Arc<T>, group: Option<u64>) {
+        if group.is_none() || self.grouped != group {
+            let previous = std::mem::replace(&mut self.current, new_state);
+            self.past.push_back(previous);
+            while self.past.len() > self.depth {
+                self.past.pop_front();
+            }
+            self.grouped = group;
+        } else {
+            self.current = new_state;
+        }
+        self.future.clear();
+        self.version += 1;
+    }
+}
+
+/// A change of the state of an [`AsyncSnapshot`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Change<T> {
+    pub old: Arc<T>,
+    pub new: Arc<T>,
+    /// Version of the snapshot after the change.
+    pub version: u64,
+}
+
+/// States recorded by an [`AsyncSnapshot`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct History<T> {
+    /// States restored by successive `undo` calls, oldest first.
+    pub past: Vec<Arc<T>>,
+    /// States restored by successive `redo` calls, next one first.
+    pub future: Vec<Arc<T>>,
 }
 
 impl<T: Clone + Send + Sync + 'static> AsyncSnapshot<T> {
+    pub const DEFAULT_HISTORY_DEPTH: usize = 100;
+    /// Number of changes buffered for each `changes` stream;
+    /// a stream falling further behind skips the oldest ones.
+    pub const CHANGES_CAPACITY: usize = 64;
+
     pub fn new(initial: T) -> Self {
+        Self::with_history_depth(initial, Self::DEFAULT_HISTORY_DEPTH)
+    }
+
+    /// Creates a snapshot keeping at most `depth` undo steps.
+    pub fn with_history_depth(initial: T, depth: usize) -> Self {
+        let initial = Arc::new(initial);
         Self {
+            latest: watch::Sender::new(initial.clone()),
+            changes: broadcast::Sender::new(Self::CHANGES_CAPACITY),
+            groups: Default::default(),
             inner: Arc::new(Mutex::new(AsyncSnapshotInner {
                 current: initial,
-                previous: None,
+                past: VecDeque::new(),
+                future: Vec::new(),
+                depth,
+                grouped: None,
+                version: 0,
+                recorder: None,
             })),
         }
     }
 
-    pub async fn update(&self, new_state: T) {
+    /// Replaces the state unconditionally, regardless of concurrent changes.
+    /// Passing an `Arc` lets the new state share data with other values.
+    pub async fn update(&self, new_state: impl Into<Arc<T>>) {
+        let mut inner = self.inner.lock().await;
+        let old = self.old_for_changes(&inner);
+        inner.replace(new_state.into(), self.open_group());
+        self.notify(&inner, old);
+    }
+
+    /// Records the current state and all the following changes,
+    /// made through any handle, into `recorder`.
+    pub async fn set_recorder(&self, recorder: Recorder<T>) {
         let mut inner = self.inner.lock().await;
-        inner.previous = Some(inner.current.clone());
-        inner.current = new_state;
+        recorder.record(inner.version, inner.current.clone());
+        inner.recorder = Some(recorder);
+    }
+
+    /// Returns the current state without waiting for writers.
+    /// Cheap enough to be called on every render.
+    pub fn get(&self) -> Arc<T> {
+        self.latest.borrow().clone()
+    }
+
+    /// Calls `f` with the current state without cloning it.
+    /// Writers are blocked while `f` runs, so keep it short.
+    pub fn read<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(&self.latest.borrow())
+    }
+
+    /// Returns a receiver always holding the latest state.
+    pub fn subscribe(&self) -> watch::Receiver<Arc<T>> {
+        self.latest.subscribe()
+    }
+
+    /// Derives a memoized projection of the state.
+    /// Must be called within a Tokio runtime.
+    pub fn select<U, F>(&self, f: F) -> Derived<U>
+    where
+        U: Clone + PartialEq + Send + Sync + 'static,
+        F: Fn(&T) -> U + Send + Sync + 'static,
+    {
+        let source = self.subscribe();
+        let initial = f(&source.borrow());
+        Derived::spawn(source, initial, move |state| Some(f(state)))
+    }
+
+    /// Returns a stream of the changes made from now on.
+    pub fn changes(&self) -> BoxStream<'static, Change<T>> {
+        stream::unfold(self.changes.subscribe(), |mut rx| async move {
+            loop {
+                match rx.recv().await {
+                    Ok(change) => return Some((change, rx)),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+        .boxed()
+    }
+
+    /// Keeps the state to be replaced only if someone listens to the changes.
+    fn old_for_changes(&self, inner: &AsyncSnapshotInner<T>) -> Option<Arc<T>> {
+        (self.changes.receiver_count() > 0).then(|| inner.current.clone())
+    }
+
+    fn open_group(&self) -> Option<u64> {
+        self.groups.lock().unwrap().current()
     }
 
+    /// Must be called while holding the lock so that
+    /// subscribers observe the changes in order.
+    fn notify(&self, inner: &AsyncSnapshotInner<T>, old: Option<Arc<T>>) {
+        self.latest.send_replace(inner.current.clone());
+        if let Some(recorder) = &inner.recorder {
+            recorder.record(inner.version, inner.current.clone());
+        }
+        if let Some(old) = old {
+            let _ = self.changes.send(Change {
+                old,
+                new: inner.current.clone(),
+                version: inner.version,
+            });
+        }
+    }
+
+    /// Version of the current state, incremented on every change.
+    pub async fn version(&self) -> u64 {
+        self.inner.lock().await.version
+    }
+
+    /// Begins a transaction on the current state,
+    /// which is cloned only once the transaction modifies it.
+    pub async fn transaction(&self) -> Transaction<T> {
+        let (state, version) = {
+            let inner = self.inner.lock().await;
+            (inner.current.clone(), inner.version)
+        };
+        Transaction::new(self.clone(), state, version)
+    }
+
+    /// Reverts to the previous state. Same as `undo`.
     pub async fn rollback(&self) -> bool {
+        self.undo().await.is_some()
+    }
+
+    /// Reverts to the previous state and returns it,
+    /// or returns `None` if there is nothing to undo.
+    pub async fn undo(&self) -> Option<Arc<T>> {
         let mut inner = self.inner.lock().await;
-        if let Some(prev) = inner.previous.take() {
-            inner.current = prev;
-            true
-        } else {
-            false
+        let previous = inner.past.pop_back()?;
+        let old = self.old_for_changes(&inner);
+        let current = std::mem::replace(&mut inner.current, previous);
+        inner.future.push(current);
+        inner.version += 1;
+        self.notify(&inner, old);
+        Some(inner.current.clone())
+    }
+
+    /// Re-applies the last undone state and returns it,
+    /// or returns `None` if there is nothing to redo.
+    pub async fn redo(&self) -> Option<Arc<T>> {
+        let mut inner = self.inner.lock().await;
+        let next = inner.future.pop()?;
+        let old = self.old_for_changes(&inner);
+        let current = std::mem::replace(&mut inner.current, next);
+        inner.past.push_back(current);
+        inner.version += 1;
+        self.notify(&inner, old);
+        Some(inner.current.clone())
+    }
+
+    pub async fn history(&self) -> History<T> {
+        let inner = self.inner.lock().await;
+        History {
+            past: inner.past.iter().cloned().collect(),
+            future: inner.future.iter().rev().cloned().collect(),
         }
     }
 
-    pub async fn current_mut<F, Fut, R>(&self, f: F) -> R
+    /// Starts grouping updates: until the returned guard is dropped,
+    /// all updates are undone and redone as a single step.
+    pub fn group(&self) -> UpdateGroup {
+        UpdateGroup::open(self.groups.clone())
+    }
+
+    /// Applies `f` to the current state and commits the result,
+    /// running `f` again on the latest state whenever
+    /// another change was committed in the meantime.
+    ///
+    /// `f` receives the shared state; it is cloned only if `f` modifies it,
+    /// e.g. through `Arc::make_mut`.
+    pub async fn current_mut<F, Fut, S, R>(&self, mut f: F) -> R
     where
-        F: FnOnce(T) -> Fut + Send,
-        Fut: Future<Output = (T, R)> + Send,
+        F: FnMut(Arc<T>) -> Fut + Send,
+        Fut: Future<Output = (S, R)> + Send,
+        S: Into<Arc<T>>,
         R: Send,
-        T: Clone + Send,
     {
-        let current = {
-            let inner = self.inner.lock().await;
-            inner.current.clone()
-        };
+        loop {
+            let mut transaction = self.transaction().await;
+            let (new_state, result) = f(transaction.shared()).await;
+            transaction.set(new_state);
+            if transaction.commit().await.is_ok() {
+                return result;
+            }
+        }
+    }
 
-        let (new_state, result) = f(current).await;
-        self.update(new_state).await;
-        result
+    /// Same as `current_mut`, but fails instead of retrying on conflict.
+    pub async fn try_current_mut<F, Fut, S, R>(&self, f: F) -> Result<R, Conflict>
+    where
+        F: FnOnce(Arc<T>) -> Fut + Send,
+        Fut: Future<Output = (S, R)> + Send,
+        S: Into<Arc<T>>,
+        R: Send,
+    {
+        let mut transaction = self.transaction().await;
+        let (new_state, result) = f(transaction.shared()).await;
+        transaction.set(new_state);
+        transaction.commit().await.map(|_| result)
     }
 }
 
@@ -58,6 +296,322 @@ impl<T: Clone + Send + Sync + 'static> Clone for AsyncSnapshot<T> {
     fn clone(&self) -> Self {
         Self {
             inner: Arc::clone(&self.inner),
+            latest: self.latest.clone(),
+            changes: self.changes.clone(),
+            groups: Arc::clone(&self.groups),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Event;
+
+    mod undo {
+        use super::*;
+
+        #[tokio::test]
+        async fn test_undo_redo() {
+            let snapshot = AsyncSnapshot::new(0);
+            for i in 1..=3 {
+                snapshot.update(i).await;
+            }
+
+            assert_eq!(snapshot.undo().await, Some(Arc::new(2)));
+            assert_eq!(snapshot.undo().await, Some(Arc::new(1)));
+            assert_eq!(snapshot.redo().await, Some(Arc::new(2)));
+            assert_eq!(
+                snapshot.history().await,
+                History {
+                    past: vec![Arc::new(0), Arc::new(1)],
+                    future: vec![Arc::new(3)],
+                }
+            );
+
+            snapshot.update(10).await;
+            assert_eq!(snapshot.redo().await, None);
+        }
+
+        #[tokio::test]
+        async fn test_depth() {
+            let snapshot = AsyncSnapshot::with_history_depth(0, 2);
+            for i in 1..=5 {
+                snapshot.update(i).await;
+            }
+
+            assert_eq!(snapshot.undo().await, Some(Arc::new(4)));
+            assert_eq!(snapshot.undo().await, Some(Arc::new(3)));
+            assert_eq!(snapshot.undo().await, None);
+        }
+
+        #[tokio::test]
+        async fn test_group() {
+            let snapshot = AsyncSnapshot::new(0);
+            let outer = snapshot.group();
+            snapshot.update(1).await;
+            let inner = snapshot.group();
+            snapshot.update(2).await;
+            drop(inner);
+            snapshot.update(3).await;
+            drop(outer);
+            snapshot.update(4).await;
+
+            assert_eq!(snapshot.undo().await, Some(Arc::new(3)));
+            assert_eq!(snapshot.undo().await, Some(Arc::new(0)));
+            assert_eq!(snapshot.redo().await, Some(Arc::new(3)));
+        }
+
+        #[tokio::test]
+        async fn test_group_closed_on_cancellation() {
+            let snapshot = AsyncSnapshot::new(0);
+            let task = {
+                let snapshot = snapshot.clone();
+                tokio::spawn(async move {
+                    let _group = snapshot.group();
+                    snapshot.update(1).await;
+                    std::future::pending::<()>().await;
+                })
+            };
+            tokio::task::yield_now().await;
+            task.abort();
+            let _ = task.await;
+            snapshot.update(2).await;
+            snapshot.update(3).await;
+
+            assert_eq!(snapshot.undo().await, Some(Arc::new(2)));
+            assert_eq!(snapshot.undo().await, Some(Arc::new(1)));
+        }
+    }
+
+    mod current_mut {
+        use super::*;
+
+        #[tokio::test]
+        async fn test_retry_on_conflict() {
+            let snapshot = AsyncSnapshot::new(0);
+            let mut runs = 0;
+            let result = snapshot
+                .current_mut(|state| {
+                    runs += 1;
+                    let interfere = (runs == 1).then(|| snapshot.clone());
+                    async move {
+                        if let Some(other) = interfere {
+                            other.update(10).await;
+                        }
+                        (*state + 1, *state)
+                    }
+                })
+                .await;
+
+            assert_eq!((runs, result), (2, 10));
+            assert_eq!(
+                snapshot.history().await.past,
+                vec![Arc::new(0), Arc::new(10)]
+            );
+            assert_eq!(snapshot.version().await, 2);
+        }
+
+        #[tokio::test]
+        async fn test_try_fails_on_conflict() {
+            let snapshot = AsyncSnapshot::new(0);
+            let other = snapshot.clone();
+            let result = snapshot
+                .try_current_mut(|state| async move {
+                    other.update(10).await;
+                    (*state + 1, ())
+                })
+                .await;
+
+            assert_eq!(
+                result,
+                Err(Conflict {
+                    expected: 0,
+                    actual: 1
+                })
+            );
+            assert_eq!(snapshot.undo().await, Some(Arc::new(0)));
+        }
+
+        #[tokio::test]
+        async fn test_shares_unmodified_state() {
+            let snapshot = AsyncSnapshot::new(vec![0; 16]);
+            let before = snapshot.get();
+            snapshot
+                .current_mut(|state| async move { (state, ()) })
+                .await;
+
+            assert!(Arc::ptr_eq(&before, &snapshot.get()));
+        }
+    }
+
+    mod get {
+        use super::*;
+
+        #[tokio::test]
+        async fn test_does_not_wait_for_writers() {
+            let snapshot = AsyncSnapshot::new(0);
+            snapshot.update(1).await;
+
+            let _writer = snapshot.inner.lock().await;
+            assert_eq!(*snapshot.get(), 1);
+            assert_eq!(snapshot.read(|state| state + 1), 2);
+        }
+    }
+
+    mod subscribe {
+        use super::*;
+
+        #[tokio::test]
+        async fn test_latest() {
+            let snapshot = AsyncSnapshot::new(0);
+            let mut rx = snapshot.subscribe();
+            snapshot.update(1).await;
+            snapshot.update(2).await;
+
+            rx.changed().await.unwrap();
+            assert_eq!(**rx.borrow_and_update(), 2);
+            snapshot.undo().await;
+            rx.changed().await.unwrap();
+            assert_eq!(**rx.borrow(), 1);
+        }
+    }
+
+    mod select {
+        use super::*;
+        use std::time::Duration;
+
+        async fn settle() {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_notifies_only_on_change() {
+            let snapshot = AsyncSnapshot::new((0, 0));
+            let first = snapshot.select(|state| state.0);
+            let mut rx = first.subscribe();
+
+            snapshot.update((0, 1)).await;
+            settle().await;
+            assert!(!rx.has_changed().unwrap());
+
+            snapshot.update((1, 1)).await;
+            settle().await;
+            assert!(rx.has_changed().unwrap());
+            assert_eq!(*rx.borrow_and_update(), 1);
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_map_filter() {
+            let snapshot = AsyncSnapshot::new(vec![1, 2]);
+            let evens = snapshot
+                .select(|state| state.iter().filter(|n| *n % 2 == 0).count())
+                .filter(|count| *count > 0)
+                .map(|count| count * 10);
+            assert_eq!(evens.get(), 10);
+
+            snapshot.update(vec![2, 4]).await;
+            settle().await;
+            assert_eq!(evens.get(), 20);
+
+            snapshot.update(vec![1]).await;
+            settle().await;
+            assert_eq!(evens.get(), 20);
+        }
+    }
+
+    mod set_recorder {
+        use super::*;
+
+        #[tokio::test]
+        async fn test() {
+            let recorder = Recorder::new(3);
+            let snapshot = AsyncSnapshot::new(0);
+            // Clones share the recorder, whenever it is set.
+            let clone = snapshot.clone();
+            snapshot.set_recorder(recorder.clone()).await;
+            clone.update(1).await;
+            triggered_by(vec![Event::KeyBuffer(vec!['a'])], snapshot.update(2)).await;
+            snapshot.undo().await;
+
+            let records = recorder.records();
+            assert_eq!(
+                records
+                    .iter()
+                    .map(|record| (record.version, *record.state))
+                    .collect::<Vec<_>>(),
+                vec![(1, 1), (2, 2), (3, 1)]
+            );
+            assert_eq!(
+                records[1].events.as_deref(),
+                Some(&vec![Event::KeyBuffer(vec!['a'])])
+            );
+            assert_eq!(records[2].events, None);
+        }
+    }
+
+    mod changes {
+        use super::*;
+
+        #[tokio::test]
+        async fn test() {
+            let snapshot = AsyncSnapshot::new(0);
+            snapshot.update(1).await;
+            let mut changes = snapshot.changes();
+            snapshot.update(2).await;
+            snapshot.undo().await;
+            snapshot.transaction().await.commit().await.unwrap();
+
+            let change = |old, new, version| Change {
+                old: Arc::new(old),
+                new: Arc::new(new),
+                version,
+            };
+            assert_eq!(changes.next().await, Some(change(1, 2, 2)));
+            assert_eq!(changes.next().await, Some(change(2, 1, 3)));
+            assert_eq!(changes.next().await, Some(change(1, 1, 4)));
+        }
+    }
+
+    mod transaction {
+        use super::*;
+
+        #[tokio::test]
+        async fn test_copy_on_write() {
+            let snapshot = AsyncSnapshot::new(vec![0; 4]);
+            let before = snapshot.get();
+
+            let transaction = snapshot.transaction().await;
+            assert!(std::ptr::eq(transaction.state(), &*before));
+            transaction.commit().await.unwrap();
+            assert!(Arc::ptr_eq(&snapshot.history().await.past[0], &before));
+
+            let mut transaction = snapshot.transaction().await;
+            transaction.state_mut()[0] = 1;
+            transaction.commit().await.unwrap();
+            assert_eq!(*before, vec![0; 4]);
+        }
+
+        #[tokio::test]
+        async fn test_cancelled_transaction_is_discarded() {
+            let snapshot = AsyncSnapshot::new(0);
+            let task = {
+                let snapshot = snapshot.clone();
+                tokio::spawn(async move {
+                    let mut transaction = snapshot.transaction().await;
+                    *transaction.state_mut() = 1;
+                    std::future::pending::<()>().await;
+                    transaction.commit().await
+                })
+            };
+            tokio::task::yield_now().await;
+            task.abort();
+            let _ = task.await;
+
+            let mut transaction = snapshot.transaction().await;
+            assert_eq!(*transaction.state(), 0);
+            *transaction.state_mut() = 2;
+            assert_eq!(transaction.commit().await, Ok(1));
         }
     }
 }
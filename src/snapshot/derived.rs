@@ -0,0 +1,74 @@
+use tokio::sync::watch;
+
+/// A memoized projection of an [`AsyncSnapshot`](super::AsyncSnapshot).
+///
+/// The projection is recomputed in a background task on every change
+/// of its source, and its subscribers are notified only when
+/// the result differs from the previous one.
+/// The task stops once the `Derived` and all its subscribers are dropped.
+#[derive(Clone)]
+pub struct Derived<U> {
+    rx: watch::Receiver<U>,
+}
+
+impl<U: Clone + PartialEq + Send + Sync + 'static> Derived<U> {
+    /// `f` returns `None` to ignore a change of the source.
+    pub(super) fn spawn<S, F>(mut source: watch::Receiver<S>, initial: U, f: F) -> Self
+    where
+        S: Send + Sync + 'static,
+        F: Fn(&S) -> Option<U> + Send + Sync + 'static,
+    {
+        let (tx, rx) = watch::channel(initial);
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    changed = source.changed() => if changed.is_err() {
+                        break;
+                    },
+                    _ = tx.closed() => break,
+                }
+                let Some(value) = f(&source.borrow_and_update()) else {
+                    continue;
+                };
+                tx.send_if_modified(|current| {
+                    if *current == value {
+                        return false;
+                    }
+                    *current = value;
+                    true
+                });
+            }
+        });
+        Self { rx }
+    }
+
+    pub fn get(&self) -> U {
+        self.rx.borrow().clone()
+    }
+
+    /// Returns a receiver notified whenever the value changes.
+    pub fn subscribe(&self) -> watch::Receiver<U> {
+        self.rx.clone()
+    }
+
+    pub fn map<V, F>(&self, f: F) -> Derived<V>
+    where
+        V: Clone + PartialEq + Send + Sync + 'static,
+        F: Fn(&U) -> V + Send + Sync + 'static,
+    {
+        let initial = f(&self.rx.borrow());
+        Derived::spawn(self.rx.clone(), initial, move |value| Some(f(value)))
+    }
+
+    /// Ignores the values not satisfying `predicate`,
+    /// keeping the last value that did (or the current one if none did yet).
+    pub fn filter<F>(&self, predicate: F) -> Derived<U>
+    where
+        F: Fn(&U) -> bool + Send + Sync + 'static,
+    {
+        let initial = self.get();
+        Derived::spawn(self.rx.clone(), initial, move |value| {
+            predicate(value).then(|| value.clone())
+        })
+    }
+}
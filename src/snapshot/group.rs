@@ -0,0 +1,47 @@
+use std::sync::{Arc, Mutex};
+
+/// Update groups open on a snapshot.
+#[derive(Default)]
+pub(super) struct Groups {
+    /// Nesting level of the open groups.
+    open: usize,
+    /// Identifies the outermost open group.
+    id: u64,
+}
+
+impl Groups {
+    /// The outermost open group, if any.
+    pub(super) fn current(&self) -> Option<u64> {
+        (self.open > 0).then_some(self.id)
+    }
+}
+
+/// Groups the updates of an [`AsyncSnapshot`](super::AsyncSnapshot) until dropped,
+/// so that they are undone and redone as a single step.
+/// Groups can be nested; the outermost one makes the step.
+///
+/// Dropping the guard closes the group, even when the task
+/// holding it is cancelled.
+#[must_use = "updates are grouped only until the guard is dropped"]
+pub struct UpdateGroup {
+    groups: Arc<Mutex<Groups>>,
+}
+
+impl UpdateGroup {
+    pub(super) fn open(groups: Arc<Mutex<Groups>>) -> Self {
+        {
+            let mut groups = groups.lock().unwrap();
+            if groups.open == 0 {
+                groups.id += 1;
+            }
+            groups.open += 1;
+        }
+        Self { groups }
+    }
+}
+
+impl Drop for UpdateGroup {
+    fn drop(&mut self) {
+        self.groups.lock().unwrap().open -= 1;
+    }
+}
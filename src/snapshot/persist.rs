@@ -0,0 +1,230 @@
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use tokio::task::JoinHandle;
+
+use super::AsyncSnapshot;
+
+type Migration = dyn Fn(u32, Value) -> anyhow::Result<Value> + Send + Sync;
+
+/// Where and in which format the state of an [`AsyncSnapshot`] is stored.
+///
+/// The state is written as JSON together with a format version.
+/// States written by an older version are passed to the migration hook
+/// before being deserialized.
+#[derive(Clone)]
+pub struct Persistence {
+    path: PathBuf,
+    version: u32,
+    migrate: Option<Arc<Migration>>,
+}
+
+impl Persistence {
+    pub fn new(path: impl Into<PathBuf>, version: u32) -> Self {
+        Self {
+            path: path.into(),
+            version,
+            migrate: None,
+        }
+    }
+
+    /// Sets the hook converting a state stored by the given older version
+    /// into the current format.
+    pub fn migrate<F>(mut self, f: F) -> Self
+    where
+        F: Fn(u32, Value) -> anyhow::Result<Value> + Send + Sync + 'static,
+    {
+        self.migrate = Some(Arc::new(f));
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads the stored state, or returns `None` if nothing was stored yet.
+    pub async fn load<T: DeserializeOwned>(&self) -> anyhow::Result<Option<T>> {
+        let bytes = match tokio::fs::read(&self.path).await {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut envelope: Value = serde_json::from_slice(&bytes)?;
+        let version = envelope
+            .get("version")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| anyhow::anyhow!("{}: missing version", self.path.display()))?
+            as u32;
+        let mut state = envelope
+            .get_mut("state")
+            .map(Value::take)
+            .ok_or_else(|| anyhow::anyhow!("{}: missing state", self.path.display()))?;
+
+        if version > self.version {
+            return Err(anyhow::anyhow!(
+                "{}: stored by a newer version ({} > {})",
+                self.path.display(),
+                version,
+                self.version
+            ));
+        }
+        if version < self.version {
+            let migrate = self.migrate.as_ref().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "{}: no migration from version {}",
+                    self.path.display(),
+                    version
+                )
+            })?;
+            state = migrate(version, state)?;
+        }
+
+        Ok(Some(serde_json::from_value(state)?))
+    }
+
+    /// Writes the state atomically, replacing the previous one.
+    pub async fn save<T: Serialize>(&self, state: &T) -> anyhow::Result<()> {
+        let mut envelope = serde_json::Map::new();
+        envelope.insert("version".into(), self.version.into());
+        envelope.insert("state".into(), serde_json::to_value(state)?);
+        let bytes = serde_json::to_vec(&envelope)?;
+
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        tokio::fs::write(&tmp, bytes).await?;
+        tokio::fs::rename(&tmp, &self.path).await?;
+        Ok(())
+    }
+}
+
+impl<T> AsyncSnapshot<T>
+where
+    T: Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    /// Creates a snapshot from the stored state, or from `initial`
+    /// if nothing was stored yet.
+    pub async fn restore(persistence: &Persistence, initial: T) -> anyhow::Result<Self> {
+        let state = persistence.load().await?.unwrap_or(initial);
+        Ok(Self::new(state))
+    }
+
+    pub async fn save(&self, persistence: &Persistence) -> anyhow::Result<()> {
+        persistence.save(&*self.get()).await
+    }
+
+    /// Saves the state whenever it changes, until all snapshots are dropped
+    /// or a save fails. Changes made during a save are coalesced.
+    pub fn save_on_change(&self, persistence: Persistence) -> JoinHandle<anyhow::Result<()>> {
+        let mut rx = self.subscribe();
+        tokio::spawn(async move {
+            while rx.changed().await.is_ok() {
+                let state = rx.borrow_and_update().clone();
+                persistence.save(&*state).await?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory of its own for each test, removed once the test ends.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(test: &str) -> Self {
+            Self(std::env::temp_dir().join(format!(
+                "promkit-async-{}-{}",
+                std::process::id(),
+                test
+            )))
+        }
+
+        fn path(&self, name: &str) -> PathBuf {
+            self.0.join(name)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    mod load {
+        use super::*;
+
+        #[tokio::test]
+        async fn test_roundtrip() {
+            let dir = TempDir::new("roundtrip");
+            let persistence = Persistence::new(dir.path("state.json"), 1);
+            assert_eq!(persistence.load::<String>().await.unwrap(), None);
+
+            let snapshot = AsyncSnapshot::restore(&persistence, String::from("a"))
+                .await
+                .unwrap();
+            snapshot.update(String::from("ab")).await;
+            snapshot.save(&persistence).await.unwrap();
+
+            let restored = AsyncSnapshot::restore(&persistence, String::new())
+                .await
+                .unwrap();
+            assert_eq!(*restored.get(), "ab");
+        }
+
+        #[tokio::test]
+        async fn test_migrate() {
+            let dir = TempDir::new("migrate");
+            let path = dir.path("state.json");
+            Persistence::new(&path, 1).save(&"query").await.unwrap();
+
+            let persistence = Persistence::new(&path, 2).migrate(|version, state| {
+                assert_eq!(version, 1);
+                Ok(serde_json::json!({ "query": state, "cursor": 0 }))
+            });
+            let state: Value = persistence.load().await.unwrap().unwrap();
+            assert_eq!(state, serde_json::json!({ "query": "query", "cursor": 0 }));
+
+            // Stored by a newer version, and no migration hook.
+            assert!(Persistence::new(&path, 0).load::<Value>().await.is_err());
+            assert!(Persistence::new(&path, 3).load::<Value>().await.is_err());
+        }
+    }
+
+    mod save_on_change {
+        use super::*;
+
+        #[tokio::test]
+        async fn test() {
+            let dir = TempDir::new("save_on_change");
+            let persistence = Persistence::new(dir.path("state.json"), 1);
+            let snapshot = AsyncSnapshot::new(String::from("a"));
+            let handle = snapshot.save_on_change(persistence.clone());
+
+            snapshot.update(String::from("ab")).await;
+            let mut saved = None;
+            for _ in 0..100 {
+                saved = persistence.load::<String>().await.unwrap();
+                if saved.is_some() {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+            assert_eq!(saved.as_deref(), Some("ab"));
+
+            drop(snapshot);
+            assert!(handle.await.unwrap().is_ok());
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use std::{
+    collections::VecDeque,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use tokio::sync::watch;
+
+use crate::Event;
+
+tokio::task_local! {
+    static TRIGGER: Arc<Vec<Event>>;
+}
+
+/// Runs `f` so that the changes it makes to recorded snapshots
+/// are attributed to `events`.
+///
+/// Evaluators are already run this way while processing event groups.
+pub async fn triggered_by<F: Future>(events: Vec<Event>, f: F) -> F::Output {
+    TRIGGER.scope(Arc::new(events), f).await
+}
+
+/// A state of an [`AsyncSnapshot`](super::AsyncSnapshot) kept by a [`Recorder`].
+#[derive(Debug)]
+pub struct Record<T> {
+    pub version: u64,
+    pub at: Instant,
+    /// The event group being processed when the change was made, if any.
+    pub events: Option<Arc<Vec<Event>>>,
+    pub state: Arc<T>,
+}
+
+impl<T> Clone for Record<T> {
+    fn clone(&self) -> Self {
+        Self {
+            version: self.version,
+            at: self.at,
+            events: self.events.clone(),
+            state: self.state.clone(),
+        }
+    }
+}
+
+/// Keeps the most recent states of the snapshots it is attached to
+/// with `AsyncSnapshot::set_recorder`.
+pub struct Recorder<T> {
+    records: Arc<Mutex<VecDeque<Record<T>>>>,
+    capacity: usize,
+    started_at: Instant,
+    /// Version of the newest record.
+    recorded: watch::Sender<Option<u64>>,
+}
+
+impl<T> Clone for Recorder<T> {
+    fn clone(&self) -> Self {
+        Self {
+            records: self.records.clone(),
+            capacity: self.capacity,
+            started_at: self.started_at,
+            recorded: self.recorded.clone(),
+        }
+    }
+}
+
+impl<T> Recorder<T> {
+    /// Creates a recorder keeping at most `capacity` records.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            records: Default::default(),
+            capacity,
+            started_at: Instant::now(),
+            recorded: watch::Sender::new(None),
+        }
+    }
+
+    /// Subscribes to the version of the newest record,
+    /// e.g. to render new records as they are made.
+    pub fn subscribe(&self) -> watch::Receiver<Option<u64>> {
+        self.recorded.subscribe()
+    }
+
+    pub fn started_at(&self) -> Instant {
+        self.started_at
+    }
+
+    /// Returns the records, oldest first.
+    pub fn records(&self) -> Vec<Record<T>> {
+        self.records.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<Record<T>> {
+        self.records.lock().unwrap().get(index).cloned()
+    }
+
+    pub(super) fn record(&self, version: u64, state: Arc<T>) {
+        let record = Record {
+            version,
+            at: Instant::now(),
+            events: TRIGGER.try_with(|events| events.clone()).ok(),
+            state,
+        };
+        {
+            let mut records = self.records.lock().unwrap();
+            records.push_back(record);
+            while records.len() > self.capacity {
+                records.pop_front();
+            }
+        }
+        self.recorded.send_replace(Some(version));
+    }
+}
@@ -0,0 +1,84 @@
+use std::{fmt, sync::Arc};
+
+use super::AsyncSnapshot;
+
+/// Returned when a commit finds the snapshot changed
+/// since its transaction began.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Conflict {
+    /// Version the transaction was based on.
+    pub expected: u64,
+    /// Version of the snapshot at commit time.
+    pub actual: u64,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "snapshot changed during transaction (expected version {}, found {})",
+            self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for Conflict {}
+
+/// A private view of the state of an [`AsyncSnapshot`],
+/// cloned on the first call to `state_mut`.
+///
+/// Changes become visible only by `commit`, which succeeds
+/// only if nobody else committed in the meantime.
+/// Dropping the transaction, e.g. when the future holding it is
+/// cancelled, discards the changes.
+pub struct Transaction<T> {
+    snapshot: AsyncSnapshot<T>,
+    state: Arc<T>,
+    version: u64,
+}
+
+impl<T: Clone + Send + Sync + 'static> Transaction<T> {
+    pub(super) fn new(snapshot: AsyncSnapshot<T>, state: Arc<T>, version: u64) -> Self {
+        Self {
+            snapshot,
+            state,
+            version,
+        }
+    }
+
+    /// Version of the snapshot this transaction is based on.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn state(&self) -> &T {
+        &self.state
+    }
+
+    pub(super) fn shared(&self) -> Arc<T> {
+        self.state.clone()
+    }
+
+    pub fn state_mut(&mut self) -> &mut T {
+        Arc::make_mut(&mut self.state)
+    }
+
+    pub fn set(&mut self, state: impl Into<Arc<T>>) {
+        self.state = state.into();
+    }
+
+    /// Publishes the state as a new version and returns it.
+    pub async fn commit(self) -> Result<u64, Conflict> {
+        let mut inner = self.snapshot.inner.lock().await;
+        if inner.version != self.version {
+            return Err(Conflict {
+                expected: self.version,
+                actual: inner.version,
+            });
+        }
+        let old = self.snapshot.old_for_changes(&inner);
+        inner.replace(self.state, self.snapshot.open_group());
+        self.snapshot.notify(&inner, old);
+        Ok(inner.version)
+    }
+}
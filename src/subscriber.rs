@@ -0,0 +1,236 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use tokio::{
+    sync::{mpsc, Notify},
+    task::JoinHandle,
+};
+
+use crate::{
+    event::{Event, EventFilter},
+    operator::TimeBasedOperator,
+};
+
+/// How event groups are queued while a component is still busy
+/// with the previous ones.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BackpressurePolicy {
+    /// Queue every group and deliver all of them in order.
+    /// The queue is unbounded: dispatching never waits,
+    /// so a component that never catches up keeps accumulating groups.
+    #[default]
+    Queue,
+    /// Keep at most the given number of pending groups,
+    /// discarding the oldest ones first.
+    /// A capacity of 0 is treated as 1, so the latest group is always delivered.
+    DropOldest(usize),
+    /// Merge all pending groups into a single group
+    /// using the operator's aggregation rules.
+    Coalesce,
+}
+
+/// A component's subscription to the event groups produced by the prompt.
+pub struct Subscriber {
+    sender: mpsc::Sender<Vec<Event>>,
+    filter: EventFilter,
+    policy: BackpressurePolicy,
+}
+
+impl From<mpsc::Sender<Vec<Event>>> for Subscriber {
+    fn from(sender: mpsc::Sender<Vec<Event>>) -> Self {
+        Self::new(sender)
+    }
+}
+
+impl Subscriber {
+    pub fn new(sender: mpsc::Sender<Vec<Event>>) -> Self {
+        Self {
+            sender,
+            filter: EventFilter::all(),
+            policy: BackpressurePolicy::default(),
+        }
+    }
+
+    pub fn filter(mut self, filter: EventFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn policy(mut self, policy: BackpressurePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Starts forwarding to the component in a background task
+    /// so that dispatching never waits for the component.
+    pub(crate) fn spawn(self) -> Dispatcher {
+        let pending = Arc::new(Mutex::new(VecDeque::new()));
+        let notify = Arc::new(Notify::new());
+
+        let handle = {
+            let pending = pending.clone();
+            let notify = notify.clone();
+            let sender = self.sender.clone();
+            tokio::spawn(async move {
+                loop {
+                    notify.notified().await;
+                    loop {
+                        let Some(events) = pending.lock().unwrap().pop_front() else {
+                            break;
+                        };
+                        if sender.send(events).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+            })
+        };
+
+        Dispatcher {
+            sender: self.sender,
+            filter: self.filter,
+            policy: self.policy,
+            pending,
+            notify,
+            handle,
+        }
+    }
+}
+
+pub(crate) struct Dispatcher {
+    sender: mpsc::Sender<Vec<Event>>,
+    filter: EventFilter,
+    policy: BackpressurePolicy,
+    pending: Arc<Mutex<VecDeque<Vec<Event>>>>,
+    notify: Arc<Notify>,
+    handle: JoinHandle<()>,
+}
+
+impl Drop for Dispatcher {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+impl Dispatcher {
+    /// Queues the events of `event_groups` accepted by the filter.
+    /// Nothing is queued when no event matches,
+    /// so the component is not woken up for irrelevant groups.
+    pub fn dispatch(&self, event_groups: &[Event]) -> anyhow::Result<()> {
+        if self.sender.is_closed() {
+            return Err(anyhow::anyhow!("Subscriber channel is closed"));
+        }
+
+        let events = self.filter.apply(event_groups);
+        if events.is_empty() {
+            return Ok(());
+        }
+
+        {
+            let mut pending = self.pending.lock().unwrap();
+            Self::enqueue(&mut pending, events, self.policy);
+        }
+        self.notify.notify_one();
+        Ok(())
+    }
+
+    fn enqueue(pending: &mut VecDeque<Vec<Event>>, events: Vec<Event>, policy: BackpressurePolicy) {
+        match policy {
+            BackpressurePolicy::Queue => pending.push_back(events),
+            BackpressurePolicy::DropOldest(capacity) => {
+                pending.push_back(events);
+                while pending.len() > capacity.max(1) {
+                    pending.pop_front();
+                }
+            }
+            BackpressurePolicy::Coalesce => {
+                pending.push_back(events);
+                let merged = TimeBasedOperator::merge_event_groups(pending.drain(..));
+                pending.push_back(merged);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod enqueue {
+        use super::*;
+
+        #[test]
+        fn test_drop_oldest() {
+            let mut pending = VecDeque::new();
+            for ch in ['a', 'b', 'c'] {
+                Dispatcher::enqueue(
+                    &mut pending,
+                    vec![Event::KeyBuffer(vec![ch])],
+                    BackpressurePolicy::DropOldest(2),
+                );
+            }
+
+            assert_eq!(
+                pending,
+                VecDeque::from(vec![
+                    vec![Event::KeyBuffer(vec!['b'])],
+                    vec![Event::KeyBuffer(vec!['c'])],
+                ])
+            );
+        }
+
+        #[test]
+        fn test_drop_oldest_with_zero_capacity() {
+            let mut pending = VecDeque::new();
+            for ch in ['a', 'b'] {
+                Dispatcher::enqueue(
+                    &mut pending,
+                    vec![Event::KeyBuffer(vec![ch])],
+                    BackpressurePolicy::DropOldest(0),
+                );
+            }
+
+            assert_eq!(
+                pending,
+                VecDeque::from(vec![vec![Event::KeyBuffer(vec!['b'])]])
+            );
+        }
+
+        #[test]
+        fn test_coalesce() {
+            let mut pending = VecDeque::new();
+            for (left, right) in [(1, 0), (0, 2), (3, 1)] {
+                Dispatcher::enqueue(
+                    &mut pending,
+                    vec![Event::HorizontalCursorBuffer(left, right)],
+                    BackpressurePolicy::Coalesce,
+                );
+            }
+
+            assert_eq!(
+                pending,
+                VecDeque::from(vec![vec![Event::HorizontalCursorBuffer(4, 3)]])
+            );
+        }
+    }
+
+    mod dispatch {
+        use super::*;
+
+        #[tokio::test]
+        async fn test_does_not_wait_for_component() {
+            let (tx, mut rx) = mpsc::channel(1);
+            let dispatcher = Subscriber::new(tx).spawn();
+
+            for ch in ['a', 'b', 'c'] {
+                dispatcher.dispatch(&[Event::KeyBuffer(vec![ch])]).unwrap();
+            }
+
+            for ch in ['a', 'b', 'c'] {
+                assert_eq!(rx.recv().await, Some(vec![Event::KeyBuffer(vec![ch])]));
+            }
+        }
+    }
+}